@@ -0,0 +1,384 @@
+use rush_core::tracker::{Span, Tracker};
+use std::rc::Rc;
+
+use crate::error::{IdError, LexerError};
+use crate::interpolation::Segments;
+use crate::state::StringState;
+use crate::token::{
+    ConditionalOperator, HereDoc, Identifier, Operator, Redirect, Segment, ShellOperator, Token,
+    Word,
+};
+use crate::Lexer;
+
+/// Returns `true` for the characters that end a shell word in command mode.
+#[inline(always)]
+pub(crate) fn is_metachar(ch: char) -> bool {
+    ch.is_whitespace() || matches!(ch, ';' | '|' | '&' | '<' | '>' | '(' | ')')
+}
+
+impl<S: Iterator<Item = char>> Lexer<'_, S> {
+    /// Reads a pipe, background or redirection operator, a redirection may be
+    /// prefixed with the file descriptor it applies to as in `2>&1`.
+    ///
+    /// `&&` and `||` are lexed as their [`ConditionalOperator`]s and the
+    /// remaining metacharacters as their [`Delimitter`]s. Returns `None` if the
+    /// source is not pointing at an operator.
+    ///
+    /// [`Delimitter`]: crate::token::Delimitter
+    pub(crate) fn read_shell_operator(&mut self) -> Option<Result<Token, LexerError>> {
+        let start = self.tracker;
+        let mut digits = 0;
+        while self
+            .source
+            .peek_nth(digits)
+            .filter(|c| c.is_ascii_digit())
+            .is_some()
+        {
+            digits += 1;
+        }
+
+        let ch = *self.source.peek_nth(digits)?;
+        if digits > 0 && !matches!(ch, '<' | '>') || !is_metachar(ch) {
+            return None;
+        }
+
+        let mut number = String::new();
+        self.take_while(&mut number, |c| c.is_ascii_digit());
+
+        let fd = match number.parse::<u32>() {
+            Ok(fd) => Some(fd),
+            Err(_) if number.is_empty() => None,
+            Err(_) => {
+                let error = IdError::IntegerOverflow(number.into());
+                return Some(Err(LexerError::new(error, self.span_from(start))));
+            },
+        };
+
+        let redirect = |redirect| Token::Shell(ShellOperator::Redirect(redirect));
+        self.bump();
+
+        let error = |lexer: &Self, error| Some(Err(LexerError::new(error, lexer.span_from(start))));
+        let token = match ch {
+            '>' if self.bump_if('>') => redirect(Redirect::Output {
+                fd: fd.unwrap_or(1),
+                append: true,
+            }),
+            '>' | '<' if self.source.peek() == Some(&'&') => {
+                self.bump();
+                let input = ch == '<';
+                let default = if input { 0 } else { 1 };
+
+                let mut target = String::new();
+                self.take_while(&mut target, |c| c.is_ascii_digit());
+
+                match target.parse() {
+                    _ if target.is_empty() && self.bump_if('-') => redirect(Redirect::Close {
+                        fd: fd.unwrap_or(default),
+                        input,
+                    }),
+                    Ok(target) => redirect(Redirect::Duplicate {
+                        fd: fd.unwrap_or(default),
+                        target,
+                        input,
+                    }),
+                    Err(_) if target.is_empty() && ch == '>' && fd.is_none() => {
+                        redirect(Redirect::OutputAndError { append: false })
+                    },
+                    Err(_) => {
+                        let literal = format!("{number}{ch}&{target}");
+                        return error(self, IdError::InvalidLiteral(literal.into()));
+                    },
+                }
+            },
+            '>' => redirect(Redirect::Output {
+                fd: fd.unwrap_or(1),
+                append: false,
+            }),
+            '<' if self.bump_if('<') => {
+                let fd = fd.unwrap_or(0);
+                if !self.bump_if('<') {
+                    return Some(self.read_heredoc(fd, start).map(Token::HereDoc));
+                }
+                redirect(Redirect::HereString { fd })
+            },
+            '<' => redirect(Redirect::Input {
+                fd: fd.unwrap_or(0),
+            }),
+            '&' if self.bump_if('>') => redirect(Redirect::OutputAndError {
+                append: self.bump_if('>'),
+            }),
+            '&' if self.bump_if('&') => {
+                Token::Operator(Operator::Conditional(ConditionalOperator::And))
+            },
+            '&' => Token::Shell(ShellOperator::Background),
+            '|' if self.bump_if('|') => {
+                Token::Operator(Operator::Conditional(ConditionalOperator::Or))
+            },
+            '|' => Token::Shell(ShellOperator::Pipe),
+            ch => match crate::token::TOKENS.get(ch.encode_utf8(&mut [0; 4])) {
+                Some(token) => token.clone(),
+                None => return error(self, IdError::UnidentifiedToken(ch.to_string().into())),
+            },
+        };
+
+        Some(Ok(token))
+    }
+
+    /// Reads the delimiter of a here-document following `<<` or `<<-` along
+    /// with its body.
+    ///
+    /// The body starts on the line following the redirection, so it is read
+    /// ahead and removed from the source leaving the rest of the current line
+    /// to be lexed as usual, the tracker skips over the body once the lexer
+    /// reaches the end of the line. Multiple here-documents on the same line
+    /// take their bodies in order.
+    fn read_heredoc(&mut self, fd: u32, start: Tracker) -> Result<HereDoc, LexerError> {
+        let error = |lexer: &Self, error| LexerError::new(error, lexer.span_from(start));
+        let strip_tabs = self.bump_if('-');
+        self.take_while(&mut String::new(), |c| c == ' ' || c == '\t');
+
+        let quoted = (0..)
+            .map_while(|n| {
+                self.source
+                    .peek_nth(n)
+                    .copied()
+                    .filter(|c| !is_metachar(*c))
+            })
+            .any(|c| matches!(c, '\'' | '\"' | '\\'));
+
+        let delimiter = match self.source.peek() {
+            Some(c) if !is_metachar(*c) => self.read_shell_word()?,
+            _ => return Err(error(self, IdError::InvalidLiteral("<<".into()))),
+        };
+
+        let Some(delimiter) = delimiter.text().map(Rc::<str>::from) else {
+            return Err(error(self, IdError::InvalidLiteral("<<".into())));
+        };
+
+        let unterminated =
+            |lexer: &Self| error(lexer, IdError::UnterminatedHereDoc(delimiter.clone()));
+        let mut newline = 0;
+        loop {
+            match self.source.peek_nth(newline) {
+                Some('\n') => break,
+                Some(_) => newline += 1,
+                None => return Err(unterminated(self)),
+            }
+        }
+
+        let mut body_start = self.tracker;
+        for n in 0..=newline {
+            self.source.advance_nth(n, &mut body_start);
+        }
+        self.skipped.chars().for_each(|ch| body_start.advance(ch));
+
+        let mut body = String::new();
+        let mut body_end = body_start;
+        let mut end = newline + 1;
+
+        loop {
+            let mut line = String::new();
+            let mut len = 0;
+            let terminated = loop {
+                match self.source.peek_nth(end + len).copied() {
+                    Some('\n') => break true,
+                    Some(ch) => line.push(ch),
+                    None => break false,
+                }
+                len += 1;
+            };
+
+            let stripped = if strip_tabs {
+                line.trim_start_matches('\t')
+            } else {
+                &line
+            };
+
+            let terminator = end + len;
+            end += len + terminated as usize;
+            if stripped == &*delimiter {
+                break;
+            }
+
+            if !terminated {
+                return Err(unterminated(self));
+            }
+
+            body.push_str(stripped);
+            if self.source.is_crlf(terminator) {
+                body.push('\r');
+            }
+            body.push('\n');
+            line.chars().for_each(|ch| body_end.advance(ch));
+            self.source.advance_nth(terminator, &mut body_end);
+        }
+
+        let drained = self.source.drain(newline + 1..end);
+        self.skipped.push_str(&drained);
+
+        let segments = if quoted {
+            vec![Segment::Text(body.replace("\r\n", "\n").into())]
+        } else {
+            Lexer::starting_at(body.chars(), body_start).read_heredoc_body()?
+        };
+
+        Ok(HereDoc::new(
+            fd,
+            delimiter,
+            segments.into(),
+            Span::new(body_start, body_end),
+            !quoted,
+            strip_tabs,
+        ))
+    }
+
+    /// Expands the body of an unquoted here-document, only `$`, `` ` ``, `\`
+    /// and a newline can be escaped with a backslash.
+    fn read_heredoc_body(&mut self) -> Result<Vec<Segment>, LexerError> {
+        let mut body = Segments::default();
+        let mut error = None;
+
+        while let Some(&ch) = self.source.peek() {
+            match ch {
+                '\\' => {
+                    self.bump();
+                    match self.source.peek().copied() {
+                        Some('\n') => {
+                            self.bump();
+                        },
+                        Some(ch @ ('$' | '`' | '\\')) => {
+                            self.bump();
+                            body.buffer.push(ch);
+                        },
+                        _ => body.buffer.push('\\'),
+                    }
+                },
+                '$' => self.push_substitution(&mut body, &mut error, true),
+                '`' => self.push_backtick_substitution(&mut body, &mut error),
+                _ => {
+                    self.bump();
+                    body.buffer.push(ch);
+                },
+            }
+        }
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(body.finish()),
+        }
+    }
+
+    /// Reads a bare shell word starting at the character the source is
+    /// currently pointing at, upto the next unquoted metacharacter.
+    ///
+    /// Quoting follows the shell, single quotes are taken verbatim, double
+    /// quotes only escape `"`, `\`, `$` and `` ` `` while still expanding
+    /// substitutions, and an unquoted backslash escapes any character. A
+    /// backslash followed by a newline continues the word on the next line.
+    pub(crate) fn read_shell_word(&mut self) -> Result<Word, LexerError> {
+        let mut word = Segments::default();
+        let mut error = None;
+        let mut glob = false;
+        let mut assignment = false;
+        let mut plain = true;
+
+        while let Some(&ch) = self.source.peek().filter(|c| !is_metachar(**c)) {
+            match ch {
+                '\\' => {
+                    self.bump();
+                    match self.bump() {
+                        Some('\n') => self.continued = self.source.peek().is_none(),
+                        Some(ch) => word.buffer.push(ch),
+                        None => word.buffer.push('\\'),
+                    }
+                    plain = false;
+                },
+                '\'' => {
+                    let start = self.tracker;
+                    self.bump();
+                    self.qstate = StringState::SQuote;
+
+                    let opening_quote = self.span_from(start);
+                    loop {
+                        match self.bump() {
+                            Some('\'') => break,
+                            Some(ch) => word.buffer.push(ch),
+                            None => return Err(self.unterminated('\'', opening_quote)),
+                        }
+                    }
+
+                    self.qstate = StringState::default();
+                    plain = false;
+                },
+                '\"' => {
+                    let start = self.tracker;
+                    self.bump();
+                    self.qstate = StringState::DQuote;
+
+                    let opening_quote = self.span_from(start);
+                    loop {
+                        match self.source.peek().copied() {
+                            None => return Err(self.unterminated('\"', opening_quote)),
+                            Some('\"') => {
+                                self.bump();
+                                break;
+                            },
+                            Some('\\') => {
+                                self.bump();
+                                match self.source.peek().copied() {
+                                    Some('\n') => {
+                                        self.bump();
+                                    },
+                                    Some(ch @ ('\"' | '\\' | '$' | '`')) => {
+                                        self.bump();
+                                        word.buffer.push(ch);
+                                    },
+                                    _ => word.buffer.push('\\'),
+                                }
+                            },
+                            Some('$') => self.push_substitution(&mut word, &mut error, true),
+                            Some('`') => self.push_backtick_substitution(&mut word, &mut error),
+                            Some(ch) => {
+                                self.bump();
+                                word.buffer.push(ch);
+                            },
+                        }
+                    }
+
+                    self.qstate = StringState::default();
+                    plain = false;
+                },
+                '$' => {
+                    self.push_substitution(&mut word, &mut error, true);
+                    plain = false;
+                },
+                '`' => {
+                    self.push_backtick_substitution(&mut word, &mut error);
+                    plain = false;
+                },
+                '=' if plain && !word.buffer.contains('=') => {
+                    self.bump();
+                    assignment =
+                        !word.buffer.is_empty() && word.buffer.parse::<Identifier>().is_ok();
+                    word.buffer.push('=');
+                },
+                _ => {
+                    self.bump();
+                    glob |= matches!(ch, '*' | '?' | '[');
+                    word.buffer.push(ch);
+                },
+            }
+        }
+
+        if let Some(e) = error {
+            return Err(e);
+        }
+
+        Ok(Word::new(word.finish().into(), glob, assignment))
+    }
+
+    fn unterminated(&mut self, quote: char, opening_quote: Span) -> LexerError {
+        self.qstate = StringState::default();
+        LexerError::new(IdError::UnterminatedString(quote), opening_quote)
+    }
+}
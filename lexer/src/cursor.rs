@@ -0,0 +1,107 @@
+use rush_core::tracker::Tracker;
+use std::collections::VecDeque;
+use std::ops::Range;
+
+/// Character source for the [`Lexer`](crate::Lexer) that can look ahead any
+/// number of characters, only the characters that have been peeked and not
+/// yet consumed are buffered.
+///
+/// Line breaks are folded into a single `'\n'`, whether they are spelled
+/// `\r\n`, `\r` or `\n` in the source, so the lexer never sees a `'\r'`. Use
+/// [`Cursor::advance_nth`] to move a [`Tracker`] past a character by its
+/// width in the source.
+pub(crate) struct Cursor<S> {
+    source: S,
+    lookahead: VecDeque<(char, bool)>,
+    stash: Option<char>,
+}
+
+impl<S: Iterator<Item = char>> Cursor<S> {
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            lookahead: VecDeque::new(),
+            stash: None,
+        }
+    }
+
+    /// Reads the next character from the source, along with whether it is a
+    /// line break folded from `\r\n`.
+    fn pull(&mut self) -> Option<(char, bool)> {
+        match self.stash.take().or_else(|| self.source.next())? {
+            '\r' => match self.source.next() {
+                Some('\n') => Some(('\n', true)),
+                next => {
+                    self.stash = next;
+                    Some(('\n', false))
+                },
+            },
+            ch => Some((ch, false)),
+        }
+    }
+
+    /// Returns the `n`th character after the current one without consuming
+    /// anything, `peek_nth(0)` is the same as [`Cursor::peek`].
+    pub fn peek_nth(&mut self, n: usize) -> Option<&char> {
+        while self.lookahead.len() <= n {
+            let ch = self.pull()?;
+            self.lookahead.push_back(ch);
+        }
+
+        self.lookahead.get(n).map(|(ch, _)| ch)
+    }
+
+    /// Moves `tracker` past the `n`th character after the current one, as it
+    /// is spelled in the source.
+    pub fn advance_nth(&mut self, n: usize, tracker: &mut Tracker) {
+        if self.peek_nth(n).is_none() {
+            return;
+        }
+
+        let (ch, crlf) = self.lookahead[n];
+        if crlf {
+            tracker.advance('\r');
+        }
+        tracker.advance(ch);
+    }
+
+    /// Returns `true` if the `n`th character ahead is a line break folded from
+    /// `\r\n`.
+    pub fn is_crlf(&mut self, n: usize) -> bool {
+        self.peek_nth(n).is_some() && self.lookahead[n].1
+    }
+
+    /// The underlying character source.
+    pub fn get_ref(&self) -> &S {
+        &self.source
+    }
+
+    pub fn peek(&mut self) -> Option<&char> {
+        self.peek_nth(0)
+    }
+
+    /// Removes the characters in `range` ahead of the current one, leaving the
+    /// ones before it in place, and returns them as they are spelled in the
+    /// source. Every character in the range must have been peeked already.
+    pub fn drain(&mut self, range: Range<usize>) -> String {
+        let mut drained = String::new();
+        for (ch, crlf) in self.lookahead.drain(range) {
+            if crlf {
+                drained.push('\r');
+            }
+            drained.push(ch);
+        }
+        drained
+    }
+}
+
+impl<S: Iterator<Item = char>> Iterator for Cursor<S> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.lookahead
+            .pop_front()
+            .or_else(|| self.pull())
+            .map(|(ch, _)| ch)
+    }
+}
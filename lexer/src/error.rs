@@ -1,8 +1,8 @@
-use crate::Tracker;
+use crate::{Span, Tracker};
 use std::{fmt, rc::Rc};
 use thiserror::Error;
 
-#[derive(Debug, Error, PartialEq, Eq)]
+#[derive(Debug, Error, PartialEq, Eq, Hash)]
 #[derive(Clone)]
 pub enum IdError {
     #[error("Could not identify token '{0}'")]
@@ -10,27 +10,74 @@ pub enum IdError {
 
     #[error("Given literal is invalid: {0}")]
     InvalidLiteral(Rc<str>),
+
+    #[error("Unterminated string, missing closing {0}")]
+    UnterminatedString(char),
+
+    #[error("Unterminated here-document, missing delimiter '{0}'")]
+    UnterminatedHereDoc(Rc<str>),
+
+    #[error("Unterminated command substitution, missing closing {0}")]
+    UnterminatedSubstitution(char),
+
+    #[error("Unterminated parameter expansion, missing closing }}")]
+    UnterminatedExpansion,
+
+    #[error("Integer literal out of range: {0}")]
+    IntegerOverflow(Rc<str>),
+
+    #[error("Invalid escape sequence '{0}'")]
+    InvalidEscape(Rc<str>),
+
+    #[error("Invalid string interpolation '{0}'")]
+    InvalidInterpolation(Rc<str>),
+}
+
+/// Problems in the source that do not stop it from being lexed.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[derive(Clone)]
+pub enum IdWarning {
+    #[error("Identifier '{0}' mixes scripts with characters that are easily confused")]
+    MixedScriptConfusable(Rc<str>),
 }
 
+/// An [`IdError`] with where it is in the source, and the text consumed by the
+/// lexer while reading the token that failed.
 #[derive(Error, Debug, PartialEq, Eq)]
 #[derive(Clone)]
-pub struct LexerError(IdError, Tracker);
+pub struct LexerError(IdError, Span, Option<Box<Span>>);
 
 impl fmt::Display for LexerError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "LexerError: {} at {:?}", self.0, self.1)
+        write!(f, "LexerError: {} at {}", self.0, self.1)
     }
 }
 
 impl LexerError {
-    pub fn new(err: IdError, at: Tracker) -> Self {
-        Self(err, at)
+    pub fn new(err: IdError, span: Span) -> Self {
+        Self(err, span, None)
+    }
+
+    pub(crate) fn with_consumed(mut self, consumed: Span) -> Self {
+        self.2 = (consumed != self.1).then(|| Box::new(consumed));
+        self
     }
 
     pub fn at(&self) -> Tracker {
+        self.1.start()
+    }
+
+    pub fn span(&self) -> Span {
         self.1
     }
 
+    /// The text consumed while reading the token that failed, which can be
+    /// larger than [`LexerError::span`], like a whole unterminated string
+    /// reported at its opening quote.
+    pub fn consumed(&self) -> Span {
+        self.2.as_deref().copied().unwrap_or(self.1)
+    }
+
     pub fn error(&self) -> IdError {
         self.0.clone()
     }
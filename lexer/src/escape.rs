@@ -0,0 +1,87 @@
+use crate::error::{IdError, LexerError};
+use crate::Lexer;
+
+impl<S: Iterator<Item = char>> Lexer<'_, S> {
+    /// Decodes a single escape sequence starting at the backslash the source is
+    /// currently pointing at.
+    ///
+    /// Supported sequences are `\n`, `\t`, `\r`, `\0`, `\\`, `\"`, `\'`, `\$`, `\xNN`
+    /// (upto `\x7F`) and `\u{NNNNNN}` (upto 6 hex digits). On failure the
+    /// returned error spans from the backslash to the last consumed character.
+    pub(crate) fn read_escape(&mut self) -> Result<char, LexerError> {
+        let start = self.tracker;
+        self.bump();
+
+        let mut sequence = String::from('\\');
+        let invalid = |lexer: &Self, sequence: &str| {
+            LexerError::new(
+                IdError::InvalidEscape(sequence.into()),
+                lexer.span_from(start),
+            )
+        };
+
+        let Some(kind) = self.source.peek().copied().filter(|c| *c != '\n') else {
+            return Err(invalid(self, &sequence));
+        };
+
+        self.bump();
+        sequence.push(kind);
+
+        match kind {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '\\' => Ok('\\'),
+            '\"' => Ok('\"'),
+            '\'' => Ok('\''),
+            '$' => Ok('$'),
+            'x' => {
+                for _ in 0..2 {
+                    match self.source.peek().copied() {
+                        Some(ch) if ch.is_ascii_hexdigit() => {
+                            self.bump();
+                            sequence.push(ch);
+                        },
+                        _ => return Err(invalid(self, &sequence)),
+                    }
+                }
+
+                u8::from_str_radix(&sequence[2..], 16)
+                    .ok()
+                    .filter(u8::is_ascii)
+                    .map(char::from)
+                    .ok_or_else(|| invalid(self, &sequence))
+            },
+            'u' => {
+                if self.source.peek() != Some(&'{') {
+                    return Err(invalid(self, &sequence));
+                }
+                self.bump();
+                sequence.push('{');
+
+                loop {
+                    match self.source.peek().copied() {
+                        Some('}') => {
+                            self.bump();
+                            sequence.push('}');
+                            break;
+                        },
+                        Some(ch) if ch.is_ascii_hexdigit() && sequence.len() < 9 => {
+                            self.bump();
+                            sequence.push(ch);
+                        },
+                        _ => return Err(invalid(self, &sequence)),
+                    }
+                }
+
+                let digits = &sequence[3..sequence.len() - 1];
+                u32::from_str_radix(digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| invalid(self, &sequence))
+            },
+            _ => Err(invalid(self, &sequence)),
+        }
+    }
+}
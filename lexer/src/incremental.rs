@@ -0,0 +1,276 @@
+use rush_core::tracker::{Spanned, Tracker};
+use std::ops::Range;
+use std::rc::Rc;
+
+use crate::state::{Mode, StringState};
+use crate::token::Token;
+use crate::Lexer;
+
+/// State of a [`Lexer`] between two tokens, enough to resume lexing from
+/// that point without going over the preceding source again.
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    tracker: Tracker,
+    qstate: StringState,
+    mode: Mode,
+    trivia: bool,
+    skipped: Rc<str>,
+}
+
+impl Checkpoint {
+    /// Position of the next character the lexer would read.
+    pub fn tracker(&self) -> Tracker {
+        self.tracker
+    }
+
+    pub fn state(&self) -> StringState {
+        self.qstate
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Returns `true` if no here-document body read ahead of time is still
+    /// waiting to be accounted for, the state is then fully described by the
+    /// position and the mode.
+    pub fn is_clean(&self) -> bool {
+        self.skipped.is_empty()
+    }
+}
+
+/// A change to a source, the bytes in `range` of the old text are replaced by
+/// `len` bytes of new text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    range: Range<usize>,
+    len: usize,
+}
+
+impl Edit {
+    /// Creates an edit replacing the bytes in `range` by `text`.
+    pub fn new(range: Range<usize>, text: &str) -> Self {
+        Self {
+            range,
+            len: text.len(),
+        }
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Number of bytes the text following the edit moves by.
+    pub fn delta(&self) -> isize {
+        self.len as isize - self.range.len() as isize
+    }
+}
+
+impl<'c, S: Iterator<Item = char>> Lexer<'c, S> {
+    /// Captures the state of the lexer, taken between two tokens it can be
+    /// handed to [`Lexer::resume`] to continue from the same point.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            tracker: self.tracker,
+            qstate: self.qstate,
+            mode: self.mode,
+            trivia: self.trivia,
+            skipped: self.skipped.as_str().into(),
+        }
+    }
+
+    /// Creates a lexer continuing from `checkpoint`, the stream must yield the
+    /// source starting at the checkpoint's offset.
+    ///
+    /// Example
+    /// ```
+    /// use lexer::Lexer;
+    ///
+    /// let string = String::from("let x = 1;");
+    /// let mut lxr = Lexer::new(Box::new(string.chars()));
+    /// lxr.next();
+    ///
+    /// let checkpoint = lxr.checkpoint();
+    /// let offset = checkpoint.tracker().get_offset();
+    /// let resumed = Lexer::resume(string[offset..].chars(), &checkpoint);
+    ///
+    /// let rest = resumed.map(|t| t.unwrap().to_string()).collect::<Vec<_>>();
+    /// assert_eq!(rest, lxr.map(|t| t.unwrap().to_string()).collect::<Vec<_>>());
+    /// ```
+    pub fn resume(stream: S, checkpoint: &Checkpoint) -> Self {
+        Self {
+            tracker: checkpoint.tracker,
+            qstate: checkpoint.qstate,
+            mode: checkpoint.mode,
+            trivia: checkpoint.trivia,
+            skipped: checkpoint.skipped.to_string(),
+            ..Self::from_chars(stream)
+        }
+    }
+
+    /// Updates `tokens`, lexed from a source before `edit` was applied to it,
+    /// by lexing only the region affected by the edit out of the new source
+    /// this lexer was created with.
+    ///
+    /// The tokens must have been produced the way [`Lexer::tokenize_all`]
+    /// produces them, by a lexer with the same mode and trivia setting as this
+    /// one. Lexing restarts at the last token starting a line before the edit,
+    /// or before the token the edit directly follows, and stops as soon as a
+    /// token starting a line after the edit lines up with one of the old
+    /// tokens, the remaining old tokens are kept with their spans shifted.
+    ///
+    /// Example
+    /// ```
+    /// use lexer::{Edit, Lexer};
+    ///
+    /// let old = "let x = 1;\nlet y = 2;\n";
+    /// let (tokens, _) = Lexer::new(Box::new(old.chars())).tokenize_all();
+    ///
+    /// let new = "let x = foo + 1;\nlet y = 2;\n";
+    /// let edit = Edit::new(8..8, "foo + ");
+    /// let tokens = Lexer::new(Box::new(new.chars())).relex(&tokens, &edit);
+    ///
+    /// let (expected, _) = Lexer::new(Box::new(new.chars())).tokenize_all();
+    /// assert_eq!(tokens.len(), expected.len());
+    /// assert_eq!(tokens[9].span, expected[9].span);
+    /// ```
+    pub fn relex(mut self, tokens: &[Spanned<Token>], edit: &Edit) -> Vec<Spanned<Token>> {
+        let is_line_start = |tracker: Tracker| tracker.get_col() == 0;
+
+        // A token ending right where the edit starts may be joined by it, like
+        // a lone `\r` by an inserted `\n`, so the line after it is not safe.
+        let joined = |n: usize| n > 0 && tokens[n - 1].span.end().get_offset() == edit.range.start;
+        let (restart, start) = match tokens.iter().enumerate().rposition(|(n, t)| {
+            let offset = t.span.start().get_offset();
+            is_line_start(t.span.start())
+                && (offset < edit.range.start || offset == edit.range.start && !joined(n))
+        }) {
+            Some(n) => (n, tokens[n].span.start()),
+            None => (0, Tracker::new()),
+        };
+
+        while self.tracker.get_offset() < start.get_offset() {
+            if self.bump().is_none() {
+                break;
+            }
+        }
+        self.tracker = start;
+
+        let mut relexed = tokens[..restart].to_vec();
+        let mut old = restart;
+        let edit_end = edit.range.start + edit.len;
+
+        for result in self {
+            let token = match result {
+                Ok(token) => token,
+                Err(e) => Spanned::new(Token::Error(e.error()), e.consumed()),
+            };
+
+            let start = token.span.start();
+            if is_line_start(start) && start.get_offset() >= edit_end {
+                let target = (start.get_offset() as isize - edit.delta()) as usize;
+                while tokens
+                    .get(old)
+                    .filter(|t| t.span.start().get_offset() < target)
+                    .is_some()
+                {
+                    old += 1;
+                }
+
+                if let Some(synced) = tokens.get(old).filter(|t| {
+                    t.span.start().get_offset() == target && is_line_start(t.span.start())
+                }) {
+                    let rows = start.get_row() as isize - synced.span.start().get_row() as isize;
+                    relexed.extend(tokens[old..].iter().cloned().map(|mut token| {
+                        token.span.shift(rows, edit.delta());
+                        token
+                    }));
+                    return relexed;
+                }
+            }
+
+            relexed.push(token);
+        }
+
+        relexed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lex(source: &str, mode: Mode) -> Vec<Spanned<Token>> {
+        Lexer::new(Box::new(source.chars()))
+            .with_mode(mode)
+            .with_trivia(true)
+            .tokenize_all()
+            .0
+    }
+
+    fn render(tokens: &[Spanned<Token>]) -> Vec<(String, usize, usize)> {
+        tokens
+            .iter()
+            .map(|t| {
+                let (start, end) = (t.span.start(), t.span.end());
+                (t.to_string(), start.get_offset(), end.get_offset())
+            })
+            .collect()
+    }
+
+    fn assert_relex(old: &str, range: Range<usize>, text: &str, mode: Mode) {
+        let mut new = old.to_owned();
+        new.replace_range(range.clone(), text);
+
+        let relexed = Lexer::new(Box::new(new.chars()))
+            .with_mode(mode)
+            .with_trivia(true)
+            .relex(&lex(old, mode), &Edit::new(range, text));
+
+        assert_eq!(render(&relexed), render(&lex(&new, mode)), "{new:?}");
+    }
+
+    #[test]
+    fn test_relex_edits() {
+        let source = "let x = 1;\n  let y = \"a\nb\";\nlet z = x + y;\n# done\n";
+
+        assert_relex(source, 8..9, "foo(2)", Mode::Expression);
+        assert_relex(source, 0..0, "\n\n", Mode::Expression);
+        assert_relex(source, 10..11, "", Mode::Expression);
+        assert_relex(source, 21..22, "\"", Mode::Expression);
+        assert_relex(source, 40..40, "ä", Mode::Expression);
+        assert_relex(source, source.len()..source.len(), "x", Mode::Expression);
+    }
+
+    #[test]
+    fn test_relex_heredoc_bodies() {
+        let source = "cat <<EOF | wc\nline $x\nEOF\necho done\n";
+
+        assert_relex(source, 20..22, "$(pwd)", Mode::Command);
+        assert_relex(source, 23..26, "EOT", Mode::Command);
+        assert_relex(source, 27..31, "printf", Mode::Command);
+    }
+
+    #[test]
+    fn test_relex_edits_joining_the_previous_token() {
+        assert_relex("let x = 1;\r?", 11..11, "\n", Mode::Expression);
+        assert_relex("echo a\r\necho b", 7..8, "", Mode::Command);
+        assert_relex("\u{feff}let x;", 3..3, "\n", Mode::Expression);
+        assert_relex("\u{feff}\nlet x;", 3..4, "", Mode::Expression);
+    }
+
+    #[test]
+    fn test_relex_shifts_tokens_after_the_edit() {
+        let old = "let x = 1;\nlet y = 2;\n";
+        let new = "let x = 10;\nlet y = 2;\n";
+
+        let tokens = lex(old, Mode::Expression);
+        let relexed = Lexer::new(Box::new(new.chars()))
+            .with_trivia(true)
+            .relex(&tokens, &Edit::new(8..9, "10"));
+
+        let last = relexed.last().unwrap().span;
+        assert_eq!(last.start().get_offset(), 22);
+        assert_eq!(last.start().get_row(), 1);
+    }
+}
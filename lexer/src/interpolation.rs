@@ -0,0 +1,542 @@
+use rush_core::tracker::{Span, Spanned, Tracker};
+use std::rc::Rc;
+
+use crate::error::{IdError, LexerError};
+use crate::token::{
+    is_identifier_continue, is_identifier_start, CommandSubstitution, ParameterExpansion,
+    ParameterOperation, Segment,
+};
+use crate::Lexer;
+
+/// Accumulates the plain text and substitutions of a string or a word.
+#[derive(Default)]
+pub(crate) struct Segments {
+    pub buffer: String,
+    pub segments: Vec<Segment>,
+}
+
+impl Segments {
+    /// Pushes a substitution after any text accumulated so far.
+    pub fn push(&mut self, segment: Segment) {
+        if !self.buffer.is_empty() {
+            self.segments
+                .push(Segment::Text(self.buffer.as_str().into()));
+            self.buffer.clear();
+        }
+        self.segments.push(segment);
+    }
+
+    /// Returns the segments along with the trailing text, an empty input
+    /// yields a single empty text segment.
+    pub fn finish(mut self) -> Vec<Segment> {
+        if !self.buffer.is_empty() || self.segments.is_empty() {
+            self.segments.push(Segment::Text(self.buffer.into()));
+        }
+        self.segments
+    }
+}
+
+impl<S: Iterator<Item = char>> Lexer<'_, S> {
+    /// Reads the substitution the source is currently pointing at into
+    /// `segments`, a `$` not followed by a substitution is kept as text. The
+    /// first error encountered is kept in `error`.
+    ///
+    /// `all_forms` is passed on to [`Self::at_parameter_expansion`], it is set
+    /// in shell words, where `${a-b}` cannot be an expression.
+    pub(crate) fn push_substitution(
+        &mut self,
+        segments: &mut Segments,
+        error: &mut Option<LexerError>,
+        all_forms: bool,
+    ) {
+        match self.read_substitution(all_forms) {
+            Some(Ok(segment)) => segments.push(segment),
+            Some(Err(e)) => {
+                error.get_or_insert(e);
+            },
+            None => segments.buffer.push('$'),
+        }
+    }
+
+    /// Reads a `` `command` `` substitution into `segments`, the first error
+    /// encountered is kept in `error`.
+    pub(crate) fn push_backtick_substitution(
+        &mut self,
+        segments: &mut Segments,
+        error: &mut Option<LexerError>,
+    ) {
+        match self.read_backtick_substitution() {
+            Ok(command) => segments.push(Segment::Command(command)),
+            Err(e) => {
+                error.get_or_insert(e);
+            },
+        }
+    }
+
+    /// Reads a `$name`, `${name:-word}`, `${ expression }` or `$(command)`
+    /// substitution starting at the `$` the source is currently pointing at.
+    ///
+    /// Returns `None` when the `$` is not followed by a substitution, the `$` is
+    /// consumed regardless and is to be treated as plain text by the caller.
+    fn read_substitution(&mut self, all_forms: bool) -> Option<Result<Segment, LexerError>> {
+        let start = self.tracker;
+        self.bump();
+
+        match self.source.peek().copied() {
+            Some('{') if self.at_parameter_expansion(0, all_forms) => {
+                Some(self.read_parameter_expansion(start).map(Segment::Parameter))
+            },
+            Some('{') => Some(self.read_expression_segment(start)),
+            Some('(') => Some(self.read_command_substitution(start).map(Segment::Command)),
+            Some(ch) if is_identifier_start(ch) => {
+                let mut name = String::new();
+                while let Some(ch) = self
+                    .source
+                    .peek()
+                    .copied()
+                    .filter(|c| is_identifier_continue(*c))
+                {
+                    self.bump();
+                    name.push(ch);
+                }
+
+                let ident = name
+                    .parse()
+                    .map_err(|e| LexerError::new(e, self.span_from(start)));
+
+                Some(
+                    ident
+                        .map(|ident| Segment::Variable(Spanned::new(ident, self.span_from(start)))),
+                )
+            },
+            _ => None,
+        }
+    }
+
+    /// Collects the source of a `${ ... }` substitution up to its matching `}`
+    /// and lexes it with a nested lexer, quotes and braces inside the
+    /// expression are balanced so `"${ "}" }"` is lexed correctly.
+    fn read_expression_segment(&mut self, start: Tracker) -> Result<Segment, LexerError> {
+        self.bump();
+
+        let inner_start = self.tracker;
+        let mut source = String::new();
+        let mut quote = None;
+        let mut depth = 0usize;
+
+        let invalid = |lexer: &Self, source: &str| {
+            LexerError::new(
+                IdError::InvalidInterpolation(
+                    format!("${{{}", source.replace("\r\n", "\n")).into(),
+                ),
+                lexer.span_from(start),
+            )
+        };
+
+        loop {
+            let Some(&ch) = self.source.peek() else {
+                return Err(invalid(self, &source));
+            };
+
+            match (quote, ch) {
+                (None, '}') if depth == 0 => {
+                    self.bump();
+                    break;
+                },
+                (Some(_), '\\') => {
+                    self.bump_raw(&mut source);
+                    self.bump_raw(&mut source);
+                    continue;
+                },
+                (Some(q), ch) if q == ch => quote = None,
+                (Some(_), _) => {},
+                (None, '\"' | '\'') => quote = Some(ch),
+                (None, '{') => depth += 1,
+                (None, '}') => depth -= 1,
+                (None, _) => {},
+            }
+
+            self.bump_raw(&mut source);
+        }
+
+        let tokens =
+            Lexer::starting_at(source.chars(), inner_start).collect::<Result<Vec<_>, _>>()?;
+
+        if tokens.is_empty() {
+            source.push('}');
+            return Err(invalid(self, &source));
+        }
+
+        Ok(Segment::Expression(tokens.into()))
+    }
+
+    /// Returns `true` if the `{` found `n` characters ahead opens a parameter
+    /// expansion rather than an expression, that is if it is directly followed
+    /// by a name and one of the expansion operators, or by `#`, a name and the
+    /// closing `}`. As in the shell, `${x-1}` is an expansion and `${ x - 1 }`
+    /// an expression.
+    ///
+    /// Without `all_forms`, only the operators starting with a `:` count, so
+    /// that `"${n-1}"` in a string of the expression language stays arithmetic
+    /// while `"${n:-1}"` is still an expansion.
+    pub(crate) fn at_parameter_expansion(&mut self, n: usize, all_forms: bool) -> bool {
+        let name_end = |lexer: &mut Self, mut n: usize| {
+            if !lexer
+                .source
+                .peek_nth(n)
+                .is_some_and(|c| is_identifier_start(*c))
+            {
+                return None;
+            }
+            while lexer
+                .source
+                .peek_nth(n)
+                .is_some_and(|c| is_identifier_continue(*c))
+            {
+                n += 1;
+            }
+            Some(n)
+        };
+
+        if self.source.peek_nth(n) != Some(&'{') {
+            return false;
+        }
+
+        if self.source.peek_nth(n + 1) == Some(&'#') {
+            return name_end(self, n + 2)
+                .is_some_and(|end| self.source.peek_nth(end) == Some(&'}'));
+        }
+
+        let Some(end) = name_end(self, n + 1) else {
+            return false;
+        };
+
+        match self.source.peek_nth(end) {
+            Some(':') => matches!(self.source.peek_nth(end + 1), Some('-' | '=' | '?' | '+')),
+            Some('-' | '=' | '?' | '+' | '#' | '%' | '/' | '^' | ',') => all_forms,
+            _ => false,
+        }
+    }
+
+    /// Reads a parameter expansion, the `$` at `start` must already be consumed
+    /// with the source pointing at the `{`, see [`Self::at_parameter_expansion`].
+    pub(crate) fn read_parameter_expansion(
+        &mut self,
+        start: Tracker,
+    ) -> Result<ParameterExpansion, LexerError> {
+        use ParameterOperation::*;
+
+        self.bump();
+        let opening = self.span_from(start);
+
+        let length = self.bump_if('#');
+        let mut name = String::new();
+        self.take_while(&mut name, is_identifier_continue);
+        let name = name
+            .parse()
+            .map_err(|e| LexerError::new(e, self.span_from(start)))?;
+
+        let operation = match length {
+            true => Length,
+            false => {
+                let null = self.bump_if(':');
+                match self.bump() {
+                    Some('-') => Default {
+                        word: self.read_parameter_word(opening, false, false)?,
+                        null,
+                    },
+                    Some('=') => Assign {
+                        word: self.read_parameter_word(opening, false, false)?,
+                        null,
+                    },
+                    Some('?') => Error {
+                        word: self.read_parameter_word(opening, false, false)?,
+                        null,
+                    },
+                    Some('+') => Alternative {
+                        word: self.read_parameter_word(opening, false, false)?,
+                        null,
+                    },
+                    Some('#') => RemovePrefix {
+                        longest: self.bump_if('#'),
+                        pattern: self.read_parameter_word(opening, true, false)?,
+                    },
+                    Some('%') => RemoveSuffix {
+                        longest: self.bump_if('%'),
+                        pattern: self.read_parameter_word(opening, true, false)?,
+                    },
+                    Some('/') => {
+                        let all = self.bump_if('/');
+                        let pattern = self.read_parameter_word(opening, true, true)?;
+                        self.bump_if('/');
+                        Replace {
+                            pattern,
+                            replacement: self.read_parameter_word(opening, false, false)?,
+                            all,
+                        }
+                    },
+                    Some('^') => Uppercase {
+                        all: self.bump_if('^'),
+                    },
+                    _ => Lowercase {
+                        all: self.bump_if(','),
+                    },
+                }
+            },
+        };
+
+        if !self.bump_if('}') {
+            let mut rest = String::new();
+            self.take_while(&mut rest, |c| c != '}');
+            if !self.bump_if('}') {
+                return Err(LexerError::new(IdError::UnterminatedExpansion, opening));
+            }
+
+            return Err(LexerError::new(
+                IdError::InvalidInterpolation(rest.into()),
+                self.span_from(start),
+            ));
+        }
+
+        Ok(ParameterExpansion::new(name, operation))
+    }
+
+    /// Reads the word of a parameter expansion upto the `}` closing it, or upto
+    /// a `/` when `slash` is set, leaving it unconsumed.
+    ///
+    /// Quoting works as in a shell word and braces are balanced. When reading a
+    /// `pattern`, the glob characters that were quoted or escaped are pushed
+    /// with a backslash in front of them so they only match themselves.
+    fn read_parameter_word(
+        &mut self,
+        opening: Span,
+        pattern: bool,
+        slash: bool,
+    ) -> Result<Rc<[Segment]>, LexerError> {
+        let mut word = Segments::default();
+        let mut error = None;
+        let mut depth = 0usize;
+
+        let quoted = |word: &mut Segments, ch: char| {
+            if pattern && matches!(ch, '*' | '?' | '[' | ']' | '\\') {
+                word.buffer.push('\\');
+            }
+            word.buffer.push(ch);
+        };
+        let unterminated = |close: char, span: Span| {
+            let error = match close {
+                '}' => IdError::UnterminatedExpansion,
+                _ => IdError::UnterminatedString(close),
+            };
+            LexerError::new(error, span)
+        };
+
+        loop {
+            let Some(ch) = self.source.peek().copied() else {
+                return Err(unterminated('}', opening));
+            };
+
+            match ch {
+                '}' if depth == 0 => break,
+                '/' if slash && depth == 0 => break,
+                '\\' => {
+                    self.bump();
+                    match self.bump() {
+                        Some(ch) => quoted(&mut word, ch),
+                        None => return Err(unterminated('}', opening)),
+                    }
+                },
+                '\'' | '\"' => {
+                    let start = self.tracker;
+                    self.bump();
+                    let opening_quote = self.span_from(start);
+
+                    loop {
+                        match self.source.peek().copied() {
+                            None => return Err(unterminated(ch, opening_quote)),
+                            Some(close) if close == ch => {
+                                self.bump();
+                                break;
+                            },
+                            Some('\\') if ch == '\"' => {
+                                self.bump();
+                                match self.source.peek().copied() {
+                                    Some(ch @ ('\"' | '\\' | '$' | '`')) => {
+                                        self.bump();
+                                        quoted(&mut word, ch);
+                                    },
+                                    _ => quoted(&mut word, '\\'),
+                                }
+                            },
+                            Some('$') if ch == '\"' => {
+                                self.push_substitution(&mut word, &mut error, true)
+                            },
+                            Some('`') if ch == '\"' => {
+                                self.push_backtick_substitution(&mut word, &mut error)
+                            },
+                            Some(ch) => {
+                                self.bump();
+                                quoted(&mut word, ch);
+                            },
+                        }
+                    }
+                },
+                '$' => self.push_substitution(&mut word, &mut error, true),
+                '`' => self.push_backtick_substitution(&mut word, &mut error),
+                _ => {
+                    match ch {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ => {},
+                    }
+                    self.bump();
+                    word.buffer.push(ch);
+                },
+            }
+        }
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(word.finish().into()),
+        }
+    }
+
+    /// Reads a `$(command)` substitution, the `$` at `start` must already be
+    /// consumed with the source pointing at the `(`.
+    ///
+    /// Parenthesis are balanced and quoted text, escapes, backticks and nested
+    /// substitutions are skipped over, so `$(echo ")" $(pwd))` is read as a
+    /// whole.
+    pub(crate) fn read_command_substitution(
+        &mut self,
+        start: Tracker,
+    ) -> Result<CommandSubstitution, LexerError> {
+        self.bump();
+
+        let opening = self.span_from(start);
+        let source_start = self.tracker;
+        let mut source = String::new();
+
+        if !self.scan_command(&mut source, ')') {
+            return Err(LexerError::new(
+                IdError::UnterminatedSubstitution(')'),
+                opening,
+            ));
+        }
+
+        let source_span = self.span_from(source_start);
+        self.bump();
+
+        Ok(CommandSubstitution::new(source.into(), source_span))
+    }
+
+    /// Reads a `` `command` `` substitution starting at the backtick the source
+    /// is currently pointing at, within the backticks a backslash only escapes
+    /// `` ` ``, `\` and `$`.
+    pub(crate) fn read_backtick_substitution(&mut self) -> Result<CommandSubstitution, LexerError> {
+        let start = self.tracker;
+        self.bump();
+
+        let opening = self.span_from(start);
+        let source_start = self.tracker;
+        let mut source = String::new();
+
+        loop {
+            match self.source.peek().copied() {
+                None => {
+                    return Err(LexerError::new(
+                        IdError::UnterminatedSubstitution('`'),
+                        opening,
+                    ))
+                },
+                Some('`') => break,
+                Some('\\') => {
+                    self.bump();
+                    match self.source.peek().copied() {
+                        Some(ch @ ('`' | '\\' | '$')) => {
+                            self.bump();
+                            source.push(ch);
+                        },
+                        _ => source.push('\\'),
+                    }
+                },
+                Some(_) => {
+                    self.bump_raw(&mut source);
+                },
+            }
+        }
+
+        let source_span = self.span_from(source_start);
+        self.bump();
+
+        Ok(CommandSubstitution::new(source.into(), source_span))
+    }
+
+    /// Copies the source of a command into `raw` upto an unquoted `close` at
+    /// the outermost level, leaving `close` unconsumed. Returns `false` if the
+    /// input ends before that.
+    fn scan_command(&mut self, raw: &mut String, close: char) -> bool {
+        let mut depth = 0usize;
+
+        loop {
+            let Some(&ch) = self.source.peek() else {
+                return false;
+            };
+
+            if ch == close && depth == 0 {
+                return true;
+            }
+
+            self.bump_raw(raw);
+
+            match ch {
+                '(' if close == ')' => depth += 1,
+                ')' if close == ')' => depth -= 1,
+                '\\' => {
+                    self.bump_raw(raw);
+                },
+                '\'' => loop {
+                    if self.bump_raw(raw).is_none() {
+                        return false;
+                    }
+                    if raw.ends_with('\'') {
+                        break;
+                    }
+                },
+                '\"' => loop {
+                    let Some(ch) = self.bump_raw(raw) else {
+                        return false;
+                    };
+
+                    match ch {
+                        '\"' => break,
+                        '\\' => {
+                            self.bump_raw(raw);
+                        },
+                        '$' if self.source.peek() == Some(&'(') => {
+                            self.bump_raw(raw);
+                            if !self.scan_command(raw, ')') {
+                                return false;
+                            }
+                            self.bump_raw(raw);
+                        },
+                        '`' => {
+                            if !self.scan_command(raw, '`') {
+                                return false;
+                            }
+                            self.bump_raw(raw);
+                        },
+                        _ => {},
+                    }
+                },
+                '`' => {
+                    if !self.scan_command(raw, '`') {
+                        return false;
+                    }
+                    self.bump_raw(raw);
+                },
+                _ => {},
+            }
+        }
+    }
+}
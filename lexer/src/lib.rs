@@ -1,20 +1,34 @@
-use std::iter::Peekable;
-
-use error::IdError;
+use cursor::Cursor;
 use error::LexerError;
-use rush_core::tracker::Tracker;
-use state::StringState;
-use token::{Literal, Token};
+use error::{IdError, IdWarning};
+use rush_core::tracker::{Span, Spanned, Tracker};
+use std::marker::PhantomData;
+use token::{Token, Trivia};
+
+pub use incremental::{Checkpoint, Edit};
+pub use source::StrSource;
+pub use state::{Mode, StringState};
+pub use status::{Completeness, Pending};
 
+mod command;
+mod cursor;
 pub mod error;
+mod escape;
+mod incremental;
+mod interpolation;
+mod number;
+mod source;
 mod state;
+mod status;
+mod string;
 pub mod token;
 
 /// Lexer class lazily generates token objects to be later used by a parser,
 /// It takes a source string and lives as long as the string slice exists
 /// in the memory.
 ///
-/// It implements the `Iterator` trait to provide lazy evaluation.
+/// It implements the `Iterator` trait to provide lazy evaluation, every token is
+/// yielded along with the [`Span`] of source text it was lexed from.
 ///
 /// Example
 /// ```
@@ -25,183 +39,1561 @@ pub mod token;
 /// let string  = String::from("let ident");
 /// let mut lxr = Lexer::new(Box::new(string.chars()));
 ///
-/// let kw = Token::Keyword(Keyword::Let);
-/// let id = Token::Identifier("ident".parse().unwrap());
+/// let kw = lxr.next().unwrap().unwrap();
+/// assert!(matches!(kw.node, Token::Keyword(Keyword::Let)));
+/// assert_eq!(kw.span.start().get_offset(), 0);
+/// assert_eq!(kw.span.end().get_offset(), 3);
+///
+/// let id = lxr.next().unwrap().unwrap();
+/// assert!(matches!(id.node, Token::Identifier(ref ident) if ident.name() == "ident"));
+/// assert_eq!(id.span.start().get_col(), 4);
+/// assert_eq!(id.span.end().get_col(), 9);
 ///
-/// assert!(matches!(lxr.next(), Some(Ok(kw))));
-/// assert!(matches!(lxr.next(), Some(Ok(id))));
-/// assert!(matches!(lxr.next(), None));
+/// assert!(lxr.next().is_none());
 /// ```
-#[allow(dead_code)]
-pub struct Lexer<'c> {
-    some_char: Option<char>,
+pub struct Lexer<'c, S = Box<dyn Iterator<Item = char> + 'c>> {
     tracker: Tracker,
     qstate: StringState,
-    source: Peekable<Box<dyn Iterator<Item = char> + 'c>>,
+    mode: Mode,
+    trivia: bool,
+    source: Cursor<S>,
+    skipped: String,
+    continued: bool,
+    warnings: Vec<Spanned<IdWarning>>,
+    // Ties `'c` to the lexer when `S` is given explicitly and does not use it.
+    stream: PhantomData<&'c str>,
 }
 
-#[allow(dead_code)]
 impl<'c> Lexer<'c> {
-    /// Generates a "source" as `Peekable<Chars>` from the given input string slice,
-    /// and holds it with the lifetime of the string slice.
+    /// Generates a "source" as a lookahead `Cursor` from the given character stream,
+    /// and holds it with the lifetime of the stream.
     ///
     /// The struct will consume the source to generate the token objects.
     pub fn new(stream: Box<dyn Iterator<Item = char> + 'c>) -> Self {
+        Self::from_chars(stream)
+    }
+}
+
+impl<'c> Lexer<'c, StrSource<'c>> {
+    /// Lexes a string slice directly, the fast path for sources that are fully
+    /// in memory.
+    ///
+    /// Besides avoiding the boxed stream, the source stays available so the
+    /// text of every token can be borrowed from it with [`Lexer::slice`] or
+    /// [`Lexer::with_text`]. The tokens themselves still own their names and
+    /// string contents, like the tokens of any other source.
+    ///
+    /// Example
+    /// ```
+    /// use lexer::Lexer;
+    ///
+    /// let string = String::from("let name = \"rush\";");
+    /// let texts = Lexer::from_source(&string)
+    ///     .with_text()
+    ///     .map(|t| t.unwrap().1)
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(texts, vec!["let", "name", "=", "\"rush\"", ";"]);
+    /// ```
+    pub fn from_source(source: &'c str) -> Self {
+        Self::from_chars(StrSource::new(source))
+    }
+
+    /// Borrows the text covered by `span` from the source.
+    pub fn slice(&self, span: Span) -> Option<&'c str> {
+        span.source_text(self.source.get_ref().text())
+    }
+
+    /// Yields every token along with the text it was lexed from, borrowed
+    /// from the source rather than copied.
+    pub fn with_text(
+        self,
+    ) -> impl Iterator<Item = Result<(Spanned<Token>, &'c str), LexerError>> + 'c {
+        let text = self.source.get_ref().text();
+        self.map(move |token| {
+            token.map(|token| {
+                let slice = token.span.source_text(text).unwrap_or_default();
+                (token, slice)
+            })
+        })
+    }
+}
+
+impl<'c, S: Iterator<Item = char>> Lexer<'c, S> {
+    /// Creates a lexer over any character iterator without boxing it.
+    pub fn from_chars(chars: S) -> Self {
         Self {
-            some_char: None,
             tracker: Tracker::new(),
-            source: stream.peekable(),
+            source: Cursor::new(chars),
             qstate: StringState::default(),
+            mode: Mode::default(),
+            trivia: false,
+            skipped: String::new(),
+            continued: false,
+            warnings: Vec::new(),
+            stream: PhantomData,
         }
     }
-}
 
-impl Iterator for Lexer<'_> {
-    type Item = Result<Token, error::LexerError>;
+    /// Sets the [`Mode`] the following tokens are lexed in.
+    ///
+    /// Example
+    /// ```
+    /// use lexer::{Lexer, Mode};
+    /// use lexer::token::Token;
+    ///
+    /// let string = String::from("ls -la /tmp/*.rs");
+    /// let words = Lexer::new(Box::new(string.chars()))
+    ///     .with_mode(Mode::Command)
+    ///     .map(|t| match t.unwrap().node {
+    ///         Token::Word(word) => word.text().unwrap().to_owned(),
+    ///         token => panic!("unexpected token {token}"),
+    ///     })
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(words, vec!["ls", "-la", "/tmp/*.rs"]);
+    /// ```
+    pub fn with_mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let mut buffer = String::new();
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
 
-        if let Some(c) = self.some_char {
-            buffer.push(c);
-            self.some_char = None;
-        }
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
 
-        while let Some(ch) = self.source.next() {
-            if cfg!(windows)
-                .then(|| {
-                    (ch == '\r' && self.source.peek().filter(|c| **c == '\n').is_some())
-                        || ch == '\n'
-                })
-                .unwrap_or_else(|| ch == '\n')
-            {
-                #[cfg(windows)]
-                if ch == '\r' {
-                    self.source.next();
-                }
-
-                let mut tracker = self.tracker;
-                tracker.add_col();
-
-                self.tracker.add_row();
-                self.tracker.set_col(0);
-
-                if self.qstate.is_squote() {
-                    return Some(Err(LexerError::new(
-                        IdError::InvalidLiteral(buffer.clone().as_str().into()),
-                        tracker,
-                    )));
-                }
-
-                if self.qstate.is_comment() {
-                    self.qstate = StringState::default();
-                }
-
-                if buffer.is_empty() {
-                    continue;
-                }
-                if !self.qstate.is_dquote() {
-                    break;
-                }
-            }
+    /// Makes the lexer lossless, whitespace, line breaks, comments and a leading
+    /// byte order mark are yielded as [`Token::Trivia`] ahead of the token they
+    /// precede rather than being skipped.
+    ///
+    /// The spans of all the yielded tokens and errors then cover the source
+    /// without gaps, so joining their text gives back the original source.
+    ///
+    /// Example
+    /// ```
+    /// use lexer::Lexer;
+    /// use lexer::token::{Token, Trivia};
+    ///
+    /// let string = String::from("let x = 1; ## the answer\n");
+    /// let text = Lexer::new(Box::new(string.chars()))
+    ///     .with_trivia(true)
+    ///     .map(|t| t.unwrap().span.source_text(&string).unwrap())
+    ///     .collect::<String>();
+    ///
+    /// assert_eq!(text, string);
+    /// ```
+    pub fn with_trivia(mut self, trivia: bool) -> Self {
+        self.trivia = trivia;
+        self
+    }
 
-            if self.qstate.is_normal() && ch == '#' {
-                self.qstate = StringState::Comment;
-            }
+    /// Warnings about the tokens yielded so far, like identifiers mixing
+    /// scripts with easily confused characters.
+    ///
+    /// Example
+    /// ```
+    /// use lexer::Lexer;
+    ///
+    /// let string = String::from("let p\u{430}ss = 1;");
+    /// let mut lxr = Lexer::new(Box::new(string.chars()));
+    /// lxr.by_ref().for_each(drop);
+    ///
+    /// assert_eq!(lxr.warnings().len(), 1);
+    /// assert_eq!(lxr.warnings()[0].span.start().get_col(), 4);
+    /// ```
+    pub fn warnings(&self) -> &[Spanned<IdWarning>] {
+        &self.warnings
+    }
+
+    /// Lexes the complete source without stopping at errors, returning every
+    /// token along with every error encountered.
+    ///
+    /// Each error also takes the place of the text consumed while reading the
+    /// failing token in the stream as a [`Token::Error`], so the tokens always
+    /// cover the source.
+    ///
+    /// Example
+    /// ```
+    /// use lexer::Lexer;
+    /// use lexer::token::Token;
+    ///
+    /// let string = String::from("let ?? = 1;");
+    /// let (tokens, errors) = Lexer::new(Box::new(string.chars())).tokenize_all();
+    ///
+    /// assert_eq!(tokens.len(), 5);
+    /// assert_eq!(errors.len(), 1);
+    /// assert!(matches!(tokens[1].node, Token::Error(_)));
+    /// assert_eq!(tokens[1].span, errors[0].span());
+    /// ```
+    pub fn tokenize_all(self) -> (Vec<Spanned<Token>>, Vec<LexerError>) {
+        let mut tokens = vec![];
+        let mut errors = vec![];
 
-            match self.qstate {
-                StringState::Comment => {
-                    continue;
+        for result in self {
+            match result {
+                Ok(token) => tokens.push(token),
+                Err(e) => {
+                    tokens.push(Spanned::new(Token::Error(e.error()), e.consumed()));
+                    errors.push(e);
                 },
-                StringState::Normal => {
-                    self.qstate = if ch == '\'' {
-                        StringState::SQuote
-                    } else if ch == '\"' {
-                        StringState::DQuote
-                    } else {
-                        self.qstate
-                    };
-
-                    if !self.qstate.is_normal() {
-                        if !buffer.is_empty() {
-                            break;
-                        }
-                        continue;
-                    }
+            }
+        }
 
-                    if ch.is_whitespace() {
-                        if buffer.is_empty() {
-                            continue;
-                        }
+        (tokens, errors)
+    }
 
-                        let result = buffer
-                            .as_str()
-                            .parse()
-                            .map_err(|e| LexerError::new(e, self.tracker));
-                        return Some(result);
-                    }
+    /// Same as [`Lexer::new`] except that positions are reported relative to
+    /// `tracker`, used to lex a fragment embedded in a larger source.
+    pub(crate) fn starting_at(stream: S, tracker: Tracker) -> Self {
+        Self {
+            tracker,
+            ..Self::from_chars(stream)
+        }
+    }
+}
 
-                    buffer.push(ch);
-                    let maybe_token = buffer
-                        .as_str()
-                        .parse()
-                        .map_err(|e| LexerError::new(e, self.tracker));
+impl<S: Iterator<Item = char>> Lexer<'_, S> {
+    /// Consumes the next character from the source, moving the tracker past it.
+    ///
+    /// Text removed from the source ahead of time, like here-document bodies,
+    /// is accounted for once the line preceding it ends.
+    fn bump(&mut self) -> Option<char> {
+        self.source.advance_nth(0, &mut self.tracker);
+        let ch = self.source.next()?;
 
-                    if self.source.peek().is_none() {
-                        return Some(maybe_token);
-                    }
+        if ch == '\n' && !self.skipped.is_empty() {
+            let skipped = std::mem::take(&mut self.skipped);
+            skipped.chars().for_each(|ch| self.tracker.advance(ch));
+        }
 
-                    if maybe_token.is_err() {
-                        self.some_char = buffer.pop();
+        Some(ch)
+    }
 
-                        let result = buffer
-                            .as_str()
-                            .parse()
-                            .map_err(|e| LexerError::new(e, self.tracker));
-                        return Some(result);
-                    }
-                },
-                StringState::SQuote if ch == '\'' => {
+    /// Same as [`Lexer::bump`], also pushing the character to `text` as it is
+    /// spelled in the source. Fragments collected this way keep their `\r\n`
+    /// line breaks, so a nested lexer over them reports the same positions.
+    fn bump_raw(&mut self, text: &mut String) -> Option<char> {
+        if self.source.is_crlf(0) {
+            text.push('\r');
+        }
+        let ch = self.bump()?;
+        text.push(ch);
+        Some(ch)
+    }
+
+    /// Consumes the next character if it is `expected`.
+    fn bump_if(&mut self, expected: char) -> bool {
+        let matched = self.source.peek() == Some(&expected);
+        if matched {
+            self.bump();
+        }
+        matched
+    }
+
+    /// Consumes characters into `buffer` for as long as they satisfy `predicate`.
+    fn take_while(&mut self, buffer: &mut String, predicate: impl Fn(char) -> bool) {
+        while let Some(ch) = self.source.peek().copied().filter(|c| predicate(*c)) {
+            self.bump();
+            buffer.push(ch);
+        }
+    }
+
+    fn span_from(&self, start: Tracker) -> Span {
+        Span::new(start, self.tracker)
+    }
+
+    fn spanned(&self, token: Token, start: Tracker) -> Spanned<Token> {
+        Spanned::new(token, self.span_from(start))
+    }
+
+    /// Skips over whitespace and `#` comments preceding the next token, when
+    /// trivia is enabled only the first run of them is consumed and returned.
+    fn skip_trivia(&mut self) -> Option<Trivia> {
+        while let Some(&ch) = self.source.peek() {
+            let trivia = match ch {
+                '#' => {
+                    self.qstate = StringState::Comment;
+                    self.bump();
+
+                    let doc = self.bump_if('#');
+                    let mut comment = String::new();
+                    self.take_while(&mut comment, |c| c != '\n');
                     self.qstate = StringState::default();
-                    let result = match get_char_type(&buffer) {
-                        Some(kind) => Ok(Token::Literal(kind)),
-                        None => Err(LexerError::new(
-                            IdError::InvalidLiteral(buffer.clone().as_str().into()),
-                            self.tracker,
-                        )),
-                    };
-
-                    return Some(result);
+
+                    match doc {
+                        true => Trivia::DocComment(comment.into()),
+                        false => Trivia::Comment(comment.into()),
+                    }
                 },
-                StringState::DQuote if ch == '\"' => {
-                    self.qstate = StringState::default();
-                    return Some(Ok(Token::Literal(Literal::String(
-                        buffer.clone().as_str().into(),
-                    ))));
+                '\n' if self.mode.is_command() => break,
+                '\n' => {
+                    self.bump();
+                    Trivia::Newline
                 },
-                StringState::DQuote | StringState::SQuote => {
-                    buffer.push(ch);
+                ch if ch.is_whitespace() => {
+                    let mut whitespace = String::new();
+                    self.take_while(&mut whitespace, |c| c != '\n' && c.is_whitespace());
+                    Trivia::Whitespace(whitespace.into())
                 },
+                _ => break,
+            };
+
+            if self.trivia {
+                return Some(trivia);
+            }
+        }
+
+        None
+    }
+
+    /// Skips the rest of an unidentifiable run of characters into `skipped`,
+    /// lexing resumes at the next whitespace, delimitter or quote so that a
+    /// single bad character does not derail the rest of the source.
+    fn recover(&mut self, skipped: &mut String) {
+        self.take_while(skipped, |ch| {
+            !ch.is_whitespace() && !token::is_delimitter(ch) && ch != '\'' && ch != '\"'
+        });
+    }
+
+    /// Reads a keyword, a word literal (`true`, `false`, `nil`) or an
+    /// identifier, warning about identifiers that mix confusable scripts.
+    fn read_identifier(&mut self) -> Result<Token, IdError> {
+        let start = self.tracker;
+        let mut word = String::new();
+        self.take_while(&mut word, token::is_identifier_continue);
+
+        let token = word.parse()?;
+        if let Token::Identifier(ident) = &token {
+            if ident.is_mixed_script_confusable() {
+                let warning = IdWarning::MixedScriptConfusable(ident.name().into());
+                self.warnings
+                    .push(Spanned::new(warning, self.span_from(start)));
+            }
+        }
+
+        Ok(token)
+    }
+
+    /// Reads the longest operator or delimitter from the [`TOKENS`] table that
+    /// the source starts with.
+    ///
+    /// [`TOKENS`]: token::TOKENS
+    fn read_symbol(&mut self) -> Result<Token, IdError> {
+        let mut symbol = String::new();
+        let mut longest = None;
+
+        for n in 0..token::MAX_SYMBOL_LEN {
+            let Some(&ch) = self
+                .source
+                .peek_nth(n)
+                .filter(|c| !c.is_alphanumeric() && !c.is_whitespace())
+            else {
+                break;
+            };
+
+            symbol.push(ch);
+            if let Some(token) = token::TOKENS.get(symbol.as_str()) {
+                longest = Some((n + 1, token.clone()));
+            }
+        }
+
+        match longest {
+            Some((len, token)) => {
+                (0..len).for_each(|_| {
+                    self.bump();
+                });
+                Ok(token)
+            },
+            None => {
+                let mut unknown = String::new();
+                unknown.extend(self.bump());
+                self.recover(&mut unknown);
+
+                Err(IdError::UnidentifiedToken(unknown.into()))
+            },
+        }
+    }
+}
+
+impl<S: Iterator<Item = char>> Iterator for Lexer<'_, S> {
+    type Item = Result<Spanned<Token>, error::LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.tracker.get_offset() == 0 && self.source.peek() == Some(&'\u{feff}') {
+            let start = self.tracker;
+            self.bump();
+            if self.trivia {
+                return Some(Ok(self.spanned(Token::Trivia(Trivia::ByteOrderMark), start)));
             }
+        }
 
-            self.tracker.add_col();
+        let start = self.tracker;
+        if let Some(trivia) = self.skip_trivia() {
+            return Some(Ok(self.spanned(Token::Trivia(trivia), start)));
         }
 
-        (!buffer.is_empty()).then(|| {
-            let result = buffer.parse();
-            result.map_err(|e| LexerError::new(e, self.tracker))
+        let start = self.tracker;
+        let next = *self.source.peek()?;
+        let token = match next {
+            '\n' if self.mode.is_command() => {
+                self.bump();
+                Ok(Token::Shell(token::ShellOperator::Newline))
+            },
+            _ if self.mode.is_command() => match self.read_shell_operator() {
+                Some(token) => token,
+                None => self.read_shell_word().map(Token::Word),
+            },
+            '`' => self.read_backtick_substitution().map(Token::Substitution),
+            '$' if self.source.peek_nth(1) == Some(&'(') => {
+                self.bump();
+                self.read_command_substitution(start)
+                    .map(Token::Substitution)
+            },
+            '$' if self.at_parameter_expansion(1, true) => {
+                self.bump();
+                self.read_parameter_expansion(start).map(Token::Parameter)
+            },
+            '\"' if (1..3).all(|n| self.source.peek_nth(n) == Some(&'\"')) => {
+                self.read_block_string().map(Token::Literal)
+            },
+            'r' if self.at_raw_string() => self.read_raw_string().map(Token::Literal),
+            '\"' => self.read_string().map(Token::Literal),
+            '\'' => self.read_char().map(Token::Literal),
+            ch => {
+                let token = if ch.is_ascii_digit() {
+                    self.read_number().map(Token::Literal)
+                } else if token::is_identifier_start(ch) {
+                    self.read_identifier()
+                } else {
+                    self.read_symbol()
+                };
+
+                token.map_err(|e| LexerError::new(e, self.span_from(start)))
+            },
+        };
+
+        Some(match token {
+            Ok(token) => Ok(self.spanned(token, start)),
+            Err(e) => Err(e.with_consumed(self.span_from(start))),
         })
     }
 }
 
-fn get_char_type(string: &str) -> Option<Literal> {
-    if let Some(string) = string.strip_prefix("\\b") {
-        string.parse::<u8>().map(|c| Literal::Char(c as char)).ok()
-    } else if let Some(string) = string.strip_prefix("\\x") {
-        u8::from_str_radix(string, 16)
-            .map(|c| Literal::Char(c as char))
-            .ok()
-    } else {
-        (string.len() == 1).then_some(Literal::Char(string.chars().next().unwrap()))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use token::{Literal, Segment};
+
+    fn lex(source: &str) -> Vec<Result<Spanned<Token>, LexerError>> {
+        Lexer::new(Box::new(source.chars())).collect()
+    }
+
+    fn offsets(source: &str) -> Vec<(usize, usize)> {
+        lex(source)
+            .into_iter()
+            .map(|t| t.unwrap().span)
+            .map(|s| (s.start().get_offset(), s.end().get_offset()))
+            .collect()
+    }
+
+    #[test]
+    fn test_token_spans() {
+        assert_eq!(
+            offsets("let x = 10;"),
+            vec![(0, 3), (4, 5), (6, 7), (8, 10), (10, 11)]
+        );
+    }
+
+    #[test]
+    fn test_spans_across_lines() {
+        let tokens = lex("a\n  bc")
+            .into_iter()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        let span = tokens[1].span;
+        assert_eq!((span.start().get_row(), span.start().get_col()), (1, 2));
+        assert_eq!((span.end().get_row(), span.end().get_col()), (1, 4));
+        assert_eq!(span.start().get_offset(), 4);
+    }
+
+    #[test]
+    fn test_quoted_spans() {
+        let source = "\"héllo\" 'c' x";
+        assert_eq!(offsets(source), vec![(0, 8), (9, 12), (13, 14)]);
+
+        let spans = lex(source).into_iter().map(|t| t.unwrap().span);
+        let texts = spans
+            .map(|s| s.source_text(source).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, vec!["\"héllo\"", "'c'", "x"]);
+    }
+
+    #[test]
+    fn test_error_span() {
+        let errors = lex("'ab\nx")
+            .into_iter()
+            .filter_map(Result::err)
+            .collect::<Vec<_>>();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].at().get_offset(), 0);
+    }
+
+    #[test]
+    fn test_escape_sequences() {
+        let literals = lex(r#""a\"b\n\t\r\0\\\'" '\x41' '\u{1F600}' '\''"#)
+            .into_iter()
+            .map(|t| t.unwrap().node)
+            .map(|t| match t {
+                Token::Literal(Literal::String(s)) => s.to_string(),
+                Token::Literal(Literal::Char(c)) => c.to_string(),
+                t => panic!("unexpected token {t}"),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(literals, vec!["a\"b\n\t\r\0\\'", "A", "\u{1F600}", "'"]);
+    }
+
+    #[test]
+    fn test_invalid_escape() {
+        let source = r#"x "ab\q cd" y '\x80' "\u{110000}""#;
+        let results = lex(source);
+
+        assert_eq!(results.len(), 5);
+        assert!(matches!(
+            results[2],
+            Ok(Spanned {
+                node: Token::Identifier(_),
+                ..
+            })
+        ));
+
+        let errors = results
+            .into_iter()
+            .filter_map(Result::err)
+            .collect::<Vec<_>>();
+
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].error(), IdError::InvalidEscape("\\q".into()));
+        assert_eq!(errors[0].span().source_text(source), Some("\\q"));
+        assert_eq!(errors[1].error(), IdError::InvalidEscape("\\x80".into()));
+        assert_eq!(errors[2].span().source_text(source), Some("\\u{110000}"));
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let source = r#""hi $name, ${ "}" + f("x") }!$ \$x""#;
+        let tokens = lex(source);
+        assert_eq!(tokens.len(), 1);
+
+        let Token::Literal(Literal::Interpolated(segments)) = &tokens[0].as_ref().unwrap().node
+        else {
+            panic!("expected an interpolated string");
+        };
+
+        assert!(matches!(&segments[0], Segment::Text(t) if &**t == "hi "));
+        assert!(matches!(&segments[1], Segment::Variable(v) if v.node.name() == "name"));
+        assert!(matches!(&segments[2], Segment::Text(t) if &**t == ", "));
+        assert!(matches!(&segments[4], Segment::Text(t) if &**t == "!$ $x"));
+
+        let Segment::Expression(tokens) = &segments[3] else {
+            panic!("expected an expression segment");
+        };
+        let texts = tokens
+            .iter()
+            .map(|t| t.span.source_text(source).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, vec!["\"}\"", "+", "f", "(", "\"x\"", ")"]);
+
+        let Segment::Variable(name) = &segments[1] else {
+            unreachable!()
+        };
+        assert_eq!(name.span.source_text(source), Some("$name"));
+    }
+
+    #[test]
+    fn test_invalid_interpolation() {
+        let errors = lex(r#""${}" "${ a"#)
+            .into_iter()
+            .filter_map(Result::err)
+            .map(|e| e.error())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            errors,
+            vec![
+                IdError::InvalidInterpolation("${}".into()),
+                IdError::UnterminatedString('"'),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_numbers() {
+        let source = "0xff 1_000 1e-3 2.5 x1 99999999999999999999 12ab 7";
+        let results = lex(source);
+
+        let literals = results
+            .iter()
+            .filter_map(|t| match t {
+                Ok(Spanned {
+                    node: Token::Literal(lit),
+                    ..
+                }) => Some(format!("{lit:?}")),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            literals,
+            vec![
+                "Number(255)",
+                "Number(1000)",
+                "Float(0.001)",
+                "Float(2.5)",
+                "Number(7)"
+            ]
+        );
+
+        let errors = results
+            .iter()
+            .filter_map(|t| t.as_ref().err())
+            .map(|e| (e.error(), e.span().source_text(source).unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    IdError::IntegerOverflow("99999999999999999999".into()),
+                    "99999999999999999999"
+                ),
+                (IdError::InvalidLiteral("12ab".into()), "12ab"),
+            ]
+        );
+    }
+
+    fn kinds(source: &str) -> Vec<String> {
+        lex(source)
+            .into_iter()
+            .map(|t| t.unwrap().node.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_ranges() {
+        assert_eq!(
+            kinds("1..5 1..=5 1.5..2"),
+            vec!["1", "..", "5", "1", "..=", "5", "1.5", "..", "2",]
+        );
+    }
+
+    #[test]
+    fn test_longest_operator_match() {
+        assert_eq!(
+            kinds("a+=b->c::d<=e!f"),
+            vec!["a", "+=", "b", "->", "c", "::", "d", "<=", "e", "!", "f",]
+        );
+    }
+
+    #[test]
+    fn test_unidentified_token() {
+        let results = lex("a ? b");
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[1].as_ref().unwrap_err().error(),
+            IdError::UnidentifiedToken("?".into())
+        );
+        assert!(results[2].is_ok());
+    }
+
+    #[test]
+    fn test_error_recovery() {
+        let source = "let a = b?c?; f(x?, \"s\")";
+        let (tokens, errors) = Lexer::new(Box::new(source.chars())).tokenize_all();
+
+        let errors = errors
+            .iter()
+            .map(|e| (e.error(), e.span().source_text(source).unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                (IdError::UnidentifiedToken("?c?".into()), "?c?"),
+                (IdError::UnidentifiedToken("?".into()), "?"),
+            ]
+        );
+
+        let texts = tokens
+            .iter()
+            .map(|t| t.span.source_text(source).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            vec!["let", "a", "=", "b", "?c?", ";", "f", "(", "x", "?", ",", "\"s\"", ")"]
+        );
+        assert!(matches!(tokens[4].node, Token::Error(_)));
+    }
+
+    #[test]
+    fn test_unterminated_strings() {
+        for source in ["x \"abc\ndef", "x '", "x \"a\\\"", "x \"${ \"}\" "] {
+            let results = lex(source);
+            assert_eq!(results.len(), 2, "{source}");
+
+            let error = results[1].as_ref().unwrap_err();
+            assert!(matches!(error.error(), IdError::UnterminatedString(_)));
+            assert_eq!(error.at().get_offset(), 2);
+            assert_eq!(error.span().len(), 1);
+        }
+    }
+
+    fn words(source: &str) -> Vec<token::Word> {
+        Lexer::new(Box::new(source.chars()))
+            .with_mode(Mode::Command)
+            .filter_map(|t| match t.unwrap().node {
+                Token::Word(word) => Some(word),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_command_words() {
+        let words = words(r#"ls -la /tmp/*.rs 'a b'c\ d "*" x=1 "k"=v a==b"#);
+        let texts = words.iter().map(|w| w.text().unwrap()).collect::<Vec<_>>();
+
+        assert_eq!(
+            texts,
+            vec![
+                "ls",
+                "-la",
+                "/tmp/*.rs",
+                "a bc d",
+                "*",
+                "x=1",
+                "k=v",
+                "a==b"
+            ]
+        );
+        assert!(words[1].is_flag());
+        assert!(words[2].is_glob());
+        assert!(!words[4].is_glob());
+        assert_eq!(words[5].assignment_name(), Some("x"));
+        assert_eq!(words[6].assignment_name(), None);
+        assert_eq!(words[7].assignment_name(), Some("a"));
+    }
+
+    #[test]
+    fn test_command_substitutions() {
+        let words = words(r#"echo "hi $USER\n" $HOME/bin 'no $x'"#);
+
+        assert!(matches!(
+            words[1].segments(),
+            [Segment::Text(a), Segment::Variable(v), Segment::Text(b)]
+                if &**a == "hi " && v.node.name() == "USER" && &**b == "\\n"
+        ));
+        assert!(matches!(
+            words[2].segments(),
+            [Segment::Variable(v), Segment::Text(t)] if v.node.name() == "HOME" && &**t == "/bin"
+        ));
+        assert_eq!(words[3].text(), Some("no $x"));
+    }
+
+    #[test]
+    fn test_command_metachars() {
+        let kinds = Lexer::new(Box::new("a;b|c".chars()))
+            .with_mode(Mode::Command)
+            .map(|t| t.unwrap().node.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(kinds[1], ";");
+        assert_eq!(kinds[3], "|");
+        assert_eq!(kinds.len(), 5);
+
+        let results = Lexer::new(Box::new("echo 'abc".chars()))
+            .with_mode(Mode::Command)
+            .collect::<Vec<_>>();
+        let error = results[1].as_ref().unwrap_err();
+        assert_eq!(error.error(), IdError::UnterminatedString('\''));
+        assert_eq!(error.at().get_offset(), 5);
+    }
+
+    #[test]
+    fn test_shell_operators() {
+        use token::{Redirect, ShellOperator};
+
+        let source = "a 2>&1 | b >> out 2> err < in & c &> all >&2 <<< s 12x>y && d || e; (f)";
+        let tokens = Lexer::new(Box::new(source.chars()))
+            .with_mode(Mode::Command)
+            .map(|t| t.unwrap())
+            .collect::<Vec<_>>();
+
+        let redirect = |r| Token::Shell(ShellOperator::Redirect(r)).to_string();
+        let kinds = tokens
+            .iter()
+            .map(|t| match &t.node {
+                Token::Word(w) => w.text().unwrap().to_owned(),
+                t => t.to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            vec![
+                "a".to_owned(),
+                redirect(Redirect::Duplicate {
+                    fd: 2,
+                    target: 1,
+                    input: false
+                }),
+                Token::Shell(ShellOperator::Pipe).to_string(),
+                "b".to_owned(),
+                redirect(Redirect::Output {
+                    fd: 1,
+                    append: true
+                }),
+                "out".to_owned(),
+                redirect(Redirect::Output {
+                    fd: 2,
+                    append: false
+                }),
+                "err".to_owned(),
+                redirect(Redirect::Input { fd: 0 }),
+                "in".to_owned(),
+                Token::Shell(ShellOperator::Background).to_string(),
+                "c".to_owned(),
+                redirect(Redirect::OutputAndError { append: false }),
+                "all".to_owned(),
+                redirect(Redirect::Duplicate {
+                    fd: 1,
+                    target: 2,
+                    input: false
+                }),
+                redirect(Redirect::HereString { fd: 0 }),
+                "s".to_owned(),
+                "12x".to_owned(),
+                redirect(Redirect::Output {
+                    fd: 1,
+                    append: false
+                }),
+                "y".to_owned(),
+                "&&".to_owned(),
+                "d".to_owned(),
+                "||".to_owned(),
+                "e".to_owned(),
+                ";".to_owned(),
+                "(".to_owned(),
+                "f".to_owned(),
+                ")".to_owned(),
+            ]
+        );
+
+        assert_eq!(tokens[1].span.source_text(source), Some("2>&1"));
+    }
+
+    #[test]
+    fn test_close_redirects() {
+        use token::{Redirect, ShellOperator};
+
+        let source = "echo >&- 2>&- <&- 3<&- -";
+        let tokens = Lexer::from_source(source)
+            .with_mode(Mode::Command)
+            .map(|t| t.unwrap())
+            .collect::<Vec<_>>();
+
+        let close =
+            |fd, input| Token::Shell(ShellOperator::Redirect(Redirect::Close { fd, input }));
+        let nodes = tokens[1..5]
+            .iter()
+            .map(|t| t.node.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            nodes,
+            [
+                close(1, false),
+                close(2, false),
+                close(0, true),
+                close(3, true)
+            ]
+        );
+
+        let spelled = tokens
+            .iter()
+            .map(|t| t.node.to_source())
+            .collect::<Vec<_>>();
+        assert_eq!(spelled, ["echo", ">&-", "2>&-", "<&-", "3<&-", "-"]);
+        assert_eq!(tokens[2].span.source_text(source), Some("2>&-"));
+    }
+
+    #[test]
+    fn test_command_newlines() {
+        let texts = |source: &str, trivia| {
+            Lexer::from_source(source)
+                .with_mode(Mode::Command)
+                .with_trivia(trivia)
+                .map(|t| t.unwrap())
+                .filter(|t| !t.node.is_trivia())
+                .map(|t| {
+                    (
+                        t.node.to_source(),
+                        t.node == Token::Shell(token::ShellOperator::Newline),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let newline = ("\n".to_owned(), true);
+        let word = |text: &str| (text.to_owned(), false);
+
+        let expected = [
+            word("echo"),
+            word("a"),
+            newline.clone(),
+            word("echo"),
+            word("b"),
+        ];
+        assert_eq!(texts("echo a\necho b", false), expected);
+        assert_eq!(texts("echo a # note\necho b", true), expected);
+        assert_eq!(
+            texts("echo \"a\nb\" c\\\nd\n", false),
+            [word("echo"), word("'a\nb'"), word("cd"), newline]
+        );
+        assert_eq!(kinds("a\nb"), ["a", "b"]);
+    }
+
+    #[test]
+    fn test_expression_mode_operators() {
+        assert_eq!(
+            kinds("a | b > c << d & e"),
+            vec!["a", "|", "b", ">", "c", "<<", "d", "&", "e",]
+        );
+    }
+
+    fn heredocs(tokens: &[Spanned<Token>]) -> Vec<&token::HereDoc> {
+        tokens
+            .iter()
+            .filter_map(|t| match &t.node {
+                Token::HereDoc(doc) => Some(doc),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_heredocs() {
+        let source =
+            "cat <<EOF | grep <<-'END' x\nhello $USER\n\\$HOME\nEOF\n\t\tquoted $x\n\tEND\nafter";
+        let tokens = Lexer::new(Box::new(source.chars()))
+            .with_mode(Mode::Command)
+            .map(|t| t.unwrap())
+            .collect::<Vec<_>>();
+
+        let texts = tokens
+            .iter()
+            .map(|t| t.span.source_text(source).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            vec![
+                "cat",
+                "<<EOF",
+                "|",
+                "grep",
+                "<<-'END'",
+                "x",
+                "\nhello $USER\n\\$HOME\nEOF\n\t\tquoted $x\n\tEND\n",
+                "after"
+            ]
+        );
+
+        let docs = heredocs(&tokens);
+        assert_eq!(docs.len(), 2);
+
+        assert!(docs[0].expand());
+        assert_eq!(docs[0].delimiter(), "EOF");
+        assert_eq!(
+            docs[0].body_span().source_text(source),
+            Some("hello $USER\n\\$HOME\n")
+        );
+        assert!(matches!(
+            docs[0].body(),
+            [Segment::Text(a), Segment::Variable(v), Segment::Text(b)]
+                if &**a == "hello " && v.node.name() == "USER" && &**b == "\n$HOME\n"
+        ));
+        assert_eq!(
+            match docs[0].body()[1] {
+                Segment::Variable(ref v) => v.span.source_text(source),
+                _ => None,
+            },
+            Some("$USER")
+        );
+
+        assert!(!docs[1].expand());
+        assert!(docs[1].strip_tabs());
+        assert_eq!(docs[1].delimiter(), "END");
+        assert!(matches!(docs[1].body(), [Segment::Text(t)] if &**t == "quoted $x\n"));
+        assert_eq!(
+            docs[1].body_span().source_text(source),
+            Some("\t\tquoted $x\n")
+        );
+
+        assert_eq!(tokens[7].span.start().get_row(), 6);
+    }
+
+    #[test]
+    fn test_unterminated_heredoc() {
+        let results = Lexer::new(Box::new("cat <<EOF\nabc\nEO".chars()))
+            .with_mode(Mode::Command)
+            .collect::<Vec<_>>();
+
+        let error = results[1].as_ref().unwrap_err();
+        assert_eq!(error.error(), IdError::UnterminatedHereDoc("EOF".into()));
+        assert_eq!(error.at().get_offset(), 4);
+    }
+
+    #[test]
+    fn test_command_substitutions_in_expressions() {
+        let source = r#"$(echo ")" $(pwd) '(' `a`) + `b \`c\` \$d \x`"#;
+        let tokens = lex(source)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+        assert_eq!(tokens.len(), 3);
+
+        let Token::Substitution(first) = &tokens[0].node else {
+            panic!("expected a substitution");
+        };
+        assert_eq!(first.source(), r#"echo ")" $(pwd) '(' `a`"#);
+        assert_eq!(
+            first.source_span().source_text(source),
+            Some(first.source())
+        );
+
+        let Token::Substitution(second) = &tokens[2].node else {
+            panic!("expected a substitution");
+        };
+        assert_eq!(second.source(), r"b `c` $d \x");
+    }
+
+    #[test]
+    fn test_command_substitutions_in_words() {
+        let words = words(r#"echo "a $(date +%s) b" x`pwd`"#);
+
+        assert!(matches!(
+            words[1].segments(),
+            [Segment::Text(_), Segment::Command(c), Segment::Text(_)] if c.source() == "date +%s"
+        ));
+        assert!(matches!(
+            words[2].segments(),
+            [Segment::Text(t), Segment::Command(c)] if &**t == "x" && c.source() == "pwd"
+        ));
+    }
+
+    #[test]
+    fn test_unterminated_substitutions() {
+        for (source, expected) in [
+            ("x $(ls \")\"", IdError::UnterminatedSubstitution(')')),
+            ("x `ls", IdError::UnterminatedSubstitution('`')),
+            ("x \"$(a\"", IdError::UnterminatedString('"')),
+        ] {
+            let results = lex(source);
+            let error = results.last().unwrap().as_ref().unwrap_err();
+
+            assert_eq!(error.error(), expected, "{source}");
+            assert_eq!(error.at().get_offset(), 2);
+        }
+    }
+
+    fn string(source: &str) -> Rc<str> {
+        match lex(source).remove(0).unwrap().node {
+            Token::Literal(Literal::String(text)) => text,
+            token => panic!("expected a string, found {token}"),
+        }
+    }
+
+    #[test]
+    fn test_raw_strings() {
+        assert_eq!(&*string(r#"r"C:\path\$HOME""#), r"C:\path\$HOME");
+        assert_eq!(&*string(r###"r#"say "hi""#"###), r#"say "hi""#);
+        assert_eq!(&*string(r###"r##"a "# b"##"###), r##"a "# b"##);
+        assert_eq!(&*string("r\"a\nb\""), "a\nb");
+
+        assert_eq!(offsets("r#\"a\n\"# x"), vec![(0, 7), (8, 9)]);
+        assert!(matches!(
+            lex("r#x")[0].as_ref().unwrap().node,
+            Token::Identifier(_)
+        ));
+
+        let error = lex("x r#\"a\"").pop().unwrap().unwrap_err();
+        assert_eq!(error.error(), IdError::UnterminatedString('"'));
+        assert_eq!(error.at().get_offset(), 2);
+    }
+
+    #[test]
+    fn test_block_strings() {
+        let source =
+            "let s = \"\"\"\n    fn main() {\n        \\\"\\\"\\\"\\t$x\n\n    }\n    \"\"\";";
+        assert_eq!(
+            &*lex(source)
+                .into_iter()
+                .map(|t| t.unwrap())
+                .filter_map(|t| match t.node {
+                    Token::Literal(Literal::String(text)) => Some(text),
+                    _ => None,
+                })
+                .next()
+                .unwrap(),
+            "fn main() {\n    \"\"\"\t$x\n\n}"
+        );
+
+        assert_eq!(&*string(r#""""a "quoted" b""""#), r#"a "quoted" b"#);
+        let spans = lex(source)
+            .into_iter()
+            .map(|t| t.unwrap().span)
+            .collect::<Vec<_>>();
+        assert_eq!(spans[3].start().get_row(), 0);
+        assert_eq!(spans[3].end().get_row(), 5);
+        assert_eq!(spans[4].start().get_col(), 7);
+
+        let error = lex("\"\"\"\n  a\n  \\q\n\"\"\"").remove(0).unwrap_err();
+        assert_eq!(error.error(), IdError::InvalidEscape("\\q".into()));
+        assert_eq!(error.span().start().get_row(), 2);
+        assert_eq!(error.span().start().get_col(), 2);
+    }
+
+    fn lossless(source: &str, mode: Mode) -> Vec<Spanned<Token>> {
+        let (tokens, _) = Lexer::new(Box::new(source.chars()))
+            .with_mode(mode)
+            .with_trivia(true)
+            .tokenize_all();
+
+        let text = tokens
+            .iter()
+            .map(|t| t.span.source_text(source).unwrap())
+            .collect::<String>();
+        assert_eq!(text, source);
+
+        tokens
+    }
+
+    #[test]
+    fn test_lossless_round_trip() {
+        let tokens = lossless(
+            "## adds one\nlet x =  \"a $b\" + 1 ?? 2; # done\n\n\tr#\"raw\"#\n",
+            Mode::Expression,
+        );
+        let trivia = tokens
+            .iter()
+            .filter_map(|t| match &t.node {
+                Token::Trivia(trivia) => Some(trivia),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert!(matches!(trivia[0], Trivia::DocComment(c) if &**c == " adds one"));
+        assert!(matches!(trivia[1], Trivia::Newline));
+        assert!(matches!(trivia[4], Trivia::Whitespace(w) if &**w == "  "));
+        assert!(trivia
+            .iter()
+            .any(|t| matches!(t, Trivia::Comment(c) if &**c == " done")));
+        assert!(tokens.iter().any(|t| matches!(t.node, Token::Error(_))));
+
+        lossless(
+            "cat <<EOF | grep x # find\n  body $y\nEOF\necho \"$(ls)\" 2>&1\n",
+            Mode::Command,
+        );
+
+        for source in [
+            "x \"a\\q",
+            "a \"\"\"b\\#]b\"\"\" c",
+            "x ${a:-b",
+            "x \"a\\q\" y",
+            "'ab' \"${",
+        ] {
+            let tokens = lossless(source, Mode::Expression);
+            assert!(
+                tokens.iter().any(|t| matches!(t.node, Token::Error(_))),
+                "{source}"
+            );
+        }
+        lossless("echo \"a\\\n$(ls", Mode::Command);
+
+        let tokens = lossless("\u{feff}let x", Mode::Expression);
+        assert_eq!(tokens[0].node, Token::Trivia(Trivia::ByteOrderMark));
+        assert_eq!(tokens[1].span.start().get_offset(), 3);
+    }
+
+    #[test]
+    fn test_trivia_is_skipped_by_default() {
+        assert!(lex("# comment\n  x ## doc")
+            .into_iter()
+            .all(|t| !matches!(t.unwrap().node, Token::Trivia(_))));
+    }
+
+    #[test]
+    fn test_unicode_identifiers() {
+        let names = lex("let größe = 数据 + gro\u{308}ße;")
+            .into_iter()
+            .filter_map(|t| match t.unwrap().node {
+                Token::Identifier(ident) => Some(ident),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(names.len(), 3);
+        assert_eq!(names[1].name(), "数据");
+        assert_eq!(names[0], names[2]);
+
+        let mut lexer = Lexer::new(Box::new("x = счёт + count".chars()));
+        lexer.by_ref().for_each(drop);
+
+        assert_eq!(lexer.warnings().len(), 0);
+
+        let mut lexer = Lexer::new(Box::new("x = \u{441}ounter + c\u{43e}unt".chars()));
+        lexer.by_ref().for_each(drop);
+
+        let spans = lexer
+            .warnings()
+            .iter()
+            .map(|w| (w.span.start().get_col(), w.span.end().get_col()))
+            .collect::<Vec<_>>();
+        assert_eq!(spans, vec![(4, 11), (14, 19)]);
+    }
+
+    #[test]
+    fn test_str_source() {
+        let source = "let größe = \"a $b\" + 0x1F;\ncat <<EOF | wc\nbody\nEOF\n";
+        let boxed = lex(source)
+            .into_iter()
+            .map(|t| t.map(|t| t.to_string()))
+            .collect::<Vec<_>>();
+
+        let (tokens, texts): (Vec<_>, Vec<_>) = Lexer::from_source(source)
+            .with_text()
+            .map(Result::unwrap)
+            .map(|(t, text)| (Ok(t.to_string()), text))
+            .unzip();
+
+        assert_eq!(tokens, boxed);
+        assert_eq!(texts[..5], ["let", "größe", "=", "\"a $b\"", "+"]);
+
+        let mut lexer = Lexer::from_source(source);
+        let span = lexer.nth(1).unwrap().unwrap().span;
+        assert_eq!(lexer.slice(span), Some("größe"));
+    }
+
+    #[test]
+    fn test_completeness() {
+        use token::{ConditionalOperator, Delimitter};
+        use Completeness::*;
+
+        let check = |source: &str, mode| Lexer::from_source(source).with_mode(mode).completeness();
+
+        for (source, expected) in [
+            ("echo hi\n", Complete),
+            ("echo 'a\n", Incomplete(Pending::String('\''))),
+            ("echo $(ls\n", Incomplete(Pending::Substitution(')'))),
+            ("echo `ls\n", Incomplete(Pending::Substitution('`'))),
+            (
+                "cat <<EOF\nbody\n",
+                Incomplete(Pending::HereDoc("EOF".into())),
+            ),
+            ("cat <<EOF\nbody\nEOF\n", Complete),
+            ("ls -la \\\n", Incomplete(Pending::LineContinuation)),
+            ("ls \\\n -la\n", Complete),
+            ("ls |\n", Incomplete(Pending::Pipe)),
+            (
+                "true &&\n",
+                Incomplete(Pending::Conditional(ConditionalOperator::And)),
+            ),
+            (
+                "( cd /tmp\n",
+                Incomplete(Pending::Bracket(Delimitter::LParen)),
+            ),
+            ("echo '{'\n", Complete),
+            (
+                "{ echo )\n",
+                Incomplete(Pending::Bracket(Delimitter::LCurly)),
+            ),
+        ] {
+            assert_eq!(check(source, Mode::Command), expected, "{source:?}");
+        }
+
+        for (source, expected) in [
+            (
+                "let x = [1, (2\n",
+                Incomplete(Pending::Bracket(Delimitter::LParen)),
+            ),
+            ("let x = [1, (2)]\n", Complete),
+            ("let s = \"\"\"\n  text\n", Incomplete(Pending::String('"'))),
+            ("let x = 1 + \\\n", Incomplete(Pending::LineContinuation)),
+            ("let x = a ||\n", Complete),
+            ("let x = ?? # {\n", Complete),
+        ] {
+            assert_eq!(check(source, Mode::Expression), expected, "{source:?}");
+        }
+    }
+
+    fn round_trip(source: &str, mode: Mode) {
+        let nodes = |source: &str| {
+            Lexer::from_source(source)
+                .with_mode(mode)
+                .map(|t| t.unwrap().node)
+                .collect::<Vec<_>>()
+        };
+
+        let tokens = nodes(source);
+        let spelled = tokens
+            .iter()
+            .map(Token::to_source)
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        assert_eq!(nodes(&spelled), tokens, "{spelled}");
+    }
+
+    #[test]
+    fn test_to_source_round_trip() {
+        round_trip(
+            r##"let x = "a\t\"$b\"\u{1}${c + 1}$(ls -l)$dé" + 'x' - '\'' * 1.5e-3 / 0b11;"##,
+            Mode::Expression,
+        );
+        round_trip(
+            r##"for i in 0..=10 { if a != b && !c { return nil; } } x::y -> z => [true]"##,
+            Mode::Expression,
+        );
+        round_trip(r##"r#"raw "str""# """\n  block\n  """"##, Mode::Expression);
+        round_trip(
+            r##"k=v 'k'=v a\ b "$x"y 'it''s' "" *.rs '*'.rs $(pwd) `date` \#x 2>&1 | c >> o &"##,
+            Mode::Command,
+        );
+        round_trip("echo \"a\nb\" 3<<<x <&4 &>> log || ( d )", Mode::Command);
+
+        let spellings = Lexer::from_source("3<&4 0>&1 <&2 >&2 1<&0")
+            .with_mode(Mode::Command)
+            .map(|t| t.unwrap().node.to_source())
+            .collect::<Vec<_>>();
+        assert_eq!(spellings, ["3<&4", "0>&1", "<&2", ">&2", "1<&0"]);
+
+        let tokens = Lexer::from_source("cat <<-'EOF' 3<<X\n\tbody\n\tEOF\nX\n")
+            .with_mode(Mode::Command)
+            .map(|t| t.unwrap())
+            .collect::<Vec<_>>();
+        let spelled = heredocs(&tokens)
+            .iter()
+            .map(|h| h.to_source())
+            .collect::<Vec<_>>();
+        assert_eq!(spelled, ["<<-'EOF'", "3<<X"]);
+    }
+
+    #[test]
+    fn test_parameter_expansions() {
+        use token::{ParameterOperation, TokenKind};
+
+        let tokens = lex("${x:-a}+${ x - 1 }+\"${x:-1}\"")
+            .into_iter()
+            .map(|t| t.unwrap().node)
+            .collect::<Vec<_>>();
+
+        let Token::Parameter(parameter) = &tokens[0] else {
+            panic!("{:?}", tokens[0])
+        };
+        assert_eq!(parameter.name().name(), "x");
+        assert!(matches!(
+            parameter.operation(),
+            ParameterOperation::Default { null: true, .. }
+        ));
+        assert_eq!(tokens[2].kind(), TokenKind::Delimitter);
+        assert!(matches!(
+            &tokens[tokens.len() - 1],
+            Token::Literal(Literal::Interpolated(s)) if matches!(&**s, [Segment::Parameter(_)])
+        ));
+
+        let segments = |source: &str, mode| {
+            Lexer::from_source(source)
+                .with_mode(mode)
+                .map(|t| match t.unwrap().node {
+                    Token::Literal(Literal::Interpolated(s)) => s,
+                    Token::Word(word) => word.segments().into(),
+                    t => panic!("{t:?}"),
+                })
+                .collect::<Vec<_>>()
+        };
+        let source = r#""${n-1}" "${a+b}" "${x=1}" "${a/b}" "${#a}""#;
+        let kinds = segments(source, Mode::Expression)
+            .iter()
+            .map(|s| matches!(&**s, [Segment::Parameter(_)]))
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, [false, false, false, false, true]);
+        assert!(segments(source, Mode::Expression)[..4]
+            .iter()
+            .all(|s| matches!(&**s, [Segment::Expression(_)])));
+        assert!(segments(source, Mode::Command)
+            .iter()
+            .all(|s| matches!(&**s, [Segment::Parameter(_)])));
+
+        round_trip(
+            r##"${#a} ${a=$b} ${a:?"no }"} ${a+x} ${a##*/} ${a%\%} ${a%%'*'.$b} ${a/\//\}} ${a^^}"##,
+            Mode::Expression,
+        );
+        round_trip(r##"x${a:-"b c"}y "${a//[!a]/${b,}}""##, Mode::Command);
+
+        assert_eq!(
+            Lexer::from_source("${a:-b")
+                .next()
+                .unwrap()
+                .unwrap_err()
+                .error(),
+            IdError::UnterminatedExpansion
+        );
+        assert_eq!(
+            Lexer::from_source("${a^x}")
+                .next()
+                .unwrap()
+                .unwrap_err()
+                .error(),
+            IdError::InvalidInterpolation("x".into())
+        );
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let positions = |source: &str, mode: Mode| {
+            Lexer::from_source(source)
+                .with_mode(mode)
+                .with_trivia(true)
+                .with_text()
+                .map(|t| t.unwrap())
+                .map(|(t, text)| {
+                    (
+                        t.node,
+                        t.span.start().get_row(),
+                        t.span.start().get_col(),
+                        text,
+                    )
+                })
+                .filter(|(node, ..)| !node.is_trivia())
+                .map(|(node, row, col, text)| (node, row, col, text.replace("\r\n", "\n")))
+                .collect::<Vec<_>>()
+        };
+
+        let source = "let x = \"a\nb\";\n# c\nx \\\n+ 1\n";
+        let expected = positions(source, Mode::Expression);
+        assert_eq!(
+            positions(&source.replace('\n', "\r\n"), Mode::Expression),
+            expected
+        );
+        assert_eq!(
+            positions(&source.replace('\n', "\r"), Mode::Expression)
+                .into_iter()
+                .map(|(node, row, col, _)| (node, row, col))
+                .collect::<Vec<_>>(),
+            expected
+                .into_iter()
+                .map(|(node, row, col, _)| (node, row, col))
+                .collect::<Vec<_>>(),
+        );
+
+        let source = "cat <<EOF | wc\nline $x\nEOF\necho a\\\nb\n";
+        let expected = positions(source, Mode::Command);
+        assert_eq!(
+            positions(&source.replace('\n', "\r\n"), Mode::Command),
+            expected
+        );
+
+        let source = "\"${\r\n a }\" \"\"\"\r\n  a\r\n  \\q\r\n\"\"\"";
+        let results = Lexer::from_source(source).collect::<Vec<_>>();
+        let Ok(Token::Literal(token::Literal::Interpolated(segments))) =
+            results[0].as_ref().map(|t| &t.node)
+        else {
+            panic!("{:?}", results[0]);
+        };
+        let token::Segment::Expression(tokens) = &segments[0] else {
+            panic!("{segments:?}");
+        };
+        assert_eq!(tokens[0].span.source_text(source), Some("a"));
+        assert_eq!(
+            results[1].as_ref().unwrap_err().span().source_text(source),
+            Some("\\q")
+        );
+
+        let source = "cat <<EOF\r\nx\r\n$y ${ z }\r\nEOF\r\n";
+        let tokens = Lexer::from_source(source)
+            .with_mode(Mode::Command)
+            .map(|t| t.unwrap())
+            .collect::<Vec<_>>();
+        let Token::HereDoc(heredoc) = &tokens[1].node else {
+            panic!("{tokens:?}");
+        };
+        let spans = heredoc
+            .body()
+            .iter()
+            .filter_map(|segment| match segment {
+                token::Segment::Variable(variable) => Some(variable.span),
+                token::Segment::Expression(tokens) => Some(tokens[0].span),
+                _ => None,
+            })
+            .map(|span| span.source_text(source))
+            .collect::<Vec<_>>();
+        assert_eq!(spans, [Some("$y"), Some("z")]);
+
+        for source in ["$(echo a\r\necho bb)", "`echo a\r\necho bb`"] {
+            let tokens = Lexer::from_source(source)
+                .with_mode(Mode::Command)
+                .map(|t| t.unwrap())
+                .collect::<Vec<_>>();
+            let Token::Word(word) = &tokens[0].node else {
+                panic!("{tokens:?}");
+            };
+            let token::Segment::Command(command) = &word.segments()[0] else {
+                panic!("{word:?}");
+            };
+            let words = command
+                .lexer()
+                .map(|t| t.unwrap().span.source_text(source))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                words,
+                [
+                    Some("echo"),
+                    Some("a"),
+                    Some("\r\n"),
+                    Some("echo"),
+                    Some("bb")
+                ]
+            );
+        }
+
+        let tokens = Lexer::from_source("\u{feff}let x")
+            .map(|t| t.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(tokens[0].node, Token::Keyword(token::Keyword::Let));
+        assert_eq!(tokens[0].span.start().get_offset(), 3);
+        assert_eq!(tokens[1].span.start().get_col(), 4);
+    }
+
+    #[test]
+    fn test_token_categories() {
+        use token::TokenKind;
+
+        let tokens = lex("a += b == c * 2 .. !d")
+            .into_iter()
+            .map(|t| t.unwrap().node)
+            .collect::<Vec<_>>();
+
+        assert_eq!(tokens[0].kind(), TokenKind::Identifier);
+        assert_eq!(tokens[6].kind(), TokenKind::Literal);
+        assert!(tokens[1].is_assignment() && tokens[1].binary_precedence().is_none());
+        assert!(tokens[3].is_comparison());
+        assert!(tokens[8].is_unary() && !tokens[5].is_unary());
+
+        let precedence = |n: usize| tokens[n].binary_precedence().unwrap();
+        assert!(precedence(5) > precedence(3) && precedence(3) > precedence(7));
+        assert_eq!(tokens[0], Token::Identifier("a".parse().unwrap()));
     }
 }
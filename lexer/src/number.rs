@@ -0,0 +1,194 @@
+use crate::error::IdError;
+use crate::token::{is_identifier_continue, Literal};
+use crate::Lexer;
+
+impl<S: Iterator<Item = char>> Lexer<'_, S> {
+    /// Scans a numeric literal starting at the digit the source is currently
+    /// pointing at, following the maximal munch rule.
+    ///
+    /// A `.` is only taken as a decimal point when followed by a digit, so
+    /// `1..5` and `1.abs()` leave the dot to the next token, likewise an `e`
+    /// is only taken as an exponent when followed by a digit or a sign and a
+    /// digit. Any identifier characters directly following the number are
+    /// consumed to be reported as part of an invalid literal.
+    pub(crate) fn read_number(&mut self) -> Result<Literal, IdError> {
+        let mut text = String::new();
+
+        let is_radix = self.source.peek() == Some(&'0')
+            && matches!(self.source.peek_nth(1), Some('x' | 'o' | 'b'));
+
+        if !is_radix {
+            self.take_while(&mut text, |ch| ch == '_' || ch.is_ascii_digit());
+
+            if self.source.peek() == Some(&'.')
+                && self
+                    .source
+                    .peek_nth(1)
+                    .filter(|c| c.is_ascii_digit())
+                    .is_some()
+            {
+                self.take_while(&mut text, |ch| ch == '.');
+                self.take_while(&mut text, |ch| ch == '_' || ch.is_ascii_digit());
+            }
+
+            let has_exponent = matches!(self.source.peek(), Some('e' | 'E'))
+                && match (
+                    self.source.peek_nth(1).copied(),
+                    self.source.peek_nth(2).copied(),
+                ) {
+                    (Some(c), _) if c.is_ascii_digit() => true,
+                    (Some('+' | '-'), Some(c)) => c.is_ascii_digit(),
+                    _ => false,
+                };
+
+            if has_exponent {
+                text.extend(self.bump());
+                text.extend(self.bump());
+                self.take_while(&mut text, |ch| ch == '_' || ch.is_ascii_digit());
+            }
+        }
+
+        self.take_while(&mut text, is_identifier_continue);
+        parse_number(&text)
+    }
+}
+
+/// Converts the text of a numeric literal to a [`Literal::Number`] or a
+/// [`Literal::Float`].
+///
+/// Integers may carry a `0x`, `0o` or `0b` radix prefix, digits can be
+/// separated with `_` and decimal numbers having a fractional part or an
+/// exponent are floats. Integers that do not fit in an `isize` are reported as
+/// [`IdError::IntegerOverflow`] rather than being reinterpreted as floats, and
+/// floats too large to be finite as [`IdError::InvalidLiteral`].
+pub(crate) fn parse_number(text: &str) -> Result<Literal, IdError> {
+    let invalid = || IdError::InvalidLiteral(text.into());
+    let radix = match text.get(..2) {
+        Some("0x") => 16,
+        Some("0o") => 8,
+        Some("0b") => 2,
+        _ => 10,
+    };
+
+    let digits = match radix {
+        10 => text,
+        _ => &text[2..],
+    };
+
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) && radix == 10 {
+        return Err(invalid());
+    }
+
+    let stripped = digits.replace('_', "");
+    if stripped.is_empty() {
+        return Err(invalid());
+    }
+
+    if radix != 10 {
+        if !stripped.chars().all(|c| c.is_digit(radix)) {
+            return Err(invalid());
+        }
+
+        return isize::from_str_radix(&stripped, radix)
+            .map(Literal::Number)
+            .map_err(|_| IdError::IntegerOverflow(text.into()));
+    }
+
+    if stripped.chars().all(|c| c.is_ascii_digit()) {
+        return stripped
+            .parse()
+            .map(Literal::Number)
+            .map_err(|_| IdError::IntegerOverflow(text.into()));
+    }
+
+    let (mantissa, exponent) = match stripped.find(['e', 'E']) {
+        Some(index) => (&stripped[..index], Some(&stripped[index + 1..])),
+        None => (stripped.as_str(), None),
+    };
+
+    let valid_mantissa = match mantissa.split_once('.') {
+        Some((whole, fraction)) => {
+            !whole.is_empty()
+                && !fraction.is_empty()
+                && whole
+                    .chars()
+                    .chain(fraction.chars())
+                    .all(|c| c.is_ascii_digit())
+        },
+        None => mantissa.chars().all(|c| c.is_ascii_digit()),
+    };
+
+    let valid_exponent = exponent.is_none_or(|exponent| {
+        let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        !exponent.is_empty() && exponent.chars().all(|c| c.is_ascii_digit())
+    });
+
+    if !valid_mantissa || !valid_exponent {
+        return Err(invalid());
+    }
+
+    match stripped.parse::<f64>() {
+        Ok(float) if float.is_finite() => Ok(Literal::Float(float)),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(text: &str) -> Option<isize> {
+        match parse_number(text) {
+            Ok(Literal::Number(n)) => Some(n),
+            _ => None,
+        }
+    }
+
+    fn float(text: &str) -> Option<f64> {
+        match parse_number(text) {
+            Ok(Literal::Float(f)) => Some(f),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(number("0"), Some(0));
+        assert_eq!(number("1_000_000"), Some(1_000_000));
+        assert_eq!(number("0xff"), Some(255));
+        assert_eq!(number("0xFF_FF"), Some(0xFFFF));
+        assert_eq!(number("0o17"), Some(15));
+        assert_eq!(number("0b1010"), Some(10));
+    }
+
+    #[test]
+    fn test_floats() {
+        assert_eq!(float("1.5"), Some(1.5));
+        assert_eq!(float("1e-3"), Some(1e-3));
+        assert_eq!(float("2.5E+2"), Some(250.0));
+        assert_eq!(float("1_000.000_1"), Some(1000.0001));
+    }
+
+    #[test]
+    fn test_invalid_numbers() {
+        for text in [
+            "0x", "0b102", "0o8", "12ab", "1e", "1.", "0x1.5", "1e400", "2.5E+309",
+        ] {
+            assert_eq!(
+                parse_number(text).unwrap_err(),
+                IdError::InvalidLiteral(text.into()),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_overflow() {
+        for text in ["9223372036854775808", "0x8000_0000_0000_0000"] {
+            assert_eq!(
+                parse_number(text).unwrap_err(),
+                IdError::IntegerOverflow(text.into())
+            );
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use std::str::Chars;
+
+/// Characters of a string slice for the [`Lexer`](crate::Lexer) to read,
+/// holding on to the slice so token text can be borrowed from it.
+#[derive(Debug, Clone)]
+pub struct StrSource<'s> {
+    text: &'s str,
+    chars: Chars<'s>,
+}
+
+impl<'s> StrSource<'s> {
+    pub fn new(text: &'s str) -> Self {
+        Self {
+            text,
+            chars: text.chars(),
+        }
+    }
+
+    /// The complete source, including the characters already read.
+    pub fn text(&self) -> &'s str {
+        self.text
+    }
+}
+
+impl Iterator for StrSource<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.chars.next()
+    }
+}
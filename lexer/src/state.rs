@@ -49,3 +49,26 @@ impl Default for StringState {
     }
 }
 
+/// Selects the grammar the [`Lexer`](crate::Lexer) tokenizes its source with.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default)]
+pub enum Mode {
+    /// Tokens of the scripting language, operators, literals, keywords and
+    /// identifiers.
+    #[default]
+    Expression,
+
+    /// Bare shell words such as `ls`, `-la` or `/tmp/*.rs`, only the shell
+    /// metacharacters are lexed as separate tokens.
+    Command,
+}
+
+impl Mode {
+    /// Returns `true` if the mode is [`Command`].
+    ///
+    /// [`Command`]: Mode::Command
+    #[must_use]
+    pub fn is_command(&self) -> bool {
+        matches!(self, Self::Command)
+    }
+}
@@ -0,0 +1,154 @@
+use std::rc::Rc;
+
+use crate::error::IdError;
+use crate::token::{ConditionalOperator, Delimitter, Operator, ShellOperator, Token};
+use crate::Lexer;
+
+/// Whether a source can be acted upon as is, or ends midway through a
+/// construct and more input has to be read first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Completeness {
+    Complete,
+    Incomplete(Pending),
+}
+
+impl Completeness {
+    /// Returns `true` if the completeness is [`Complete`].
+    ///
+    /// [`Complete`]: Completeness::Complete
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        matches!(self, Self::Complete)
+    }
+}
+
+/// The construct left open at the end of an incomplete source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pending {
+    /// A string or character literal missing its closing quote.
+    String(char),
+
+    /// An opening bracket, `(`, `[` or `{`, that is never closed.
+    Bracket(Delimitter),
+
+    /// A here-document missing the line with its delimiter.
+    HereDoc(Rc<str>),
+
+    /// A command substitution missing its closing `)` or `` ` ``, or a
+    /// parameter expansion missing its closing `}`.
+    Substitution(char),
+
+    /// A backslash escaping the final line break.
+    LineContinuation,
+
+    /// A pipe without a command following it.
+    Pipe,
+
+    /// A `&&` or `||` without a command following it.
+    Conditional(ConditionalOperator),
+}
+
+impl<S: Iterator<Item = char>> Lexer<'_, S> {
+    /// Lexes the rest of the source to find out if it is complete, so that an
+    /// interactive prompt can keep reading lines until it is.
+    ///
+    /// Errors that can only be fixed by more input, like an unterminated
+    /// string, make the source incomplete, every other error is left to be
+    /// reported when the complete source is lexed again. Brackets are matched
+    /// by kind, in command mode a standalone `{` or `}` word also counts.
+    ///
+    /// Example
+    /// ```
+    /// use lexer::{Completeness, Lexer, Mode, Pending};
+    /// use lexer::token::Delimitter;
+    ///
+    /// let check = |source: &str| Lexer::from_source(source).with_mode(Mode::Command).completeness();
+    ///
+    /// assert_eq!(check("if x {\n"), Completeness::Incomplete(Pending::Bracket(Delimitter::LCurly)));
+    /// assert_eq!(check("echo \"hello\n"), Completeness::Incomplete(Pending::String('"')));
+    /// assert_eq!(check("if x {\n  echo \"hello\"\n}\n"), Completeness::Complete);
+    /// ```
+    pub fn completeness(mut self) -> Completeness {
+        let mut brackets = vec![];
+        let mut last = None;
+
+        for result in self.by_ref() {
+            let token = match result {
+                Ok(token) => token,
+                Err(e) => match e.error() {
+                    IdError::UnterminatedString(quote) => {
+                        return Completeness::Incomplete(Pending::String(quote))
+                    },
+                    IdError::UnterminatedHereDoc(delimiter) => {
+                        return Completeness::Incomplete(Pending::HereDoc(delimiter))
+                    },
+                    IdError::UnterminatedSubstitution(close) => {
+                        return Completeness::Incomplete(Pending::Substitution(close))
+                    },
+                    IdError::UnterminatedExpansion => {
+                        return Completeness::Incomplete(Pending::Substitution('}'))
+                    },
+                    _ => continue,
+                },
+            };
+
+            let bracket = match &token.node {
+                Token::Delimitter(delimitter) => Some(delimitter.clone()),
+                Token::Word(word) if token.span.len() == 1 => match word.text() {
+                    Some("{") => Some(Delimitter::LCurly),
+                    Some("}") => Some(Delimitter::RCurly),
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            match bracket {
+                Some(open @ (Delimitter::LParen | Delimitter::LSquare | Delimitter::LCurly)) => {
+                    brackets.push(open)
+                },
+                Some(close @ (Delimitter::RParen | Delimitter::RSquare | Delimitter::RCurly)) => {
+                    let open = match close {
+                        Delimitter::RParen => Delimitter::LParen,
+                        Delimitter::RSquare => Delimitter::LSquare,
+                        _ => Delimitter::LCurly,
+                    };
+
+                    if brackets.last() == Some(&open) {
+                        brackets.pop();
+                    }
+                },
+                _ => {},
+            }
+
+            if !matches!(
+                token.node,
+                Token::Trivia(_) | Token::Shell(ShellOperator::Newline)
+            ) {
+                last = Some(token.node);
+            }
+        }
+
+        if self.continued {
+            return Completeness::Incomplete(Pending::LineContinuation);
+        }
+
+        if let Some(open) = brackets.pop() {
+            return Completeness::Incomplete(Pending::Bracket(open));
+        }
+
+        match last {
+            Some(Token::Delimitter(Delimitter::BackSlash)) => {
+                Completeness::Incomplete(Pending::LineContinuation)
+            },
+            Some(Token::Shell(ShellOperator::Pipe)) if self.mode.is_command() => {
+                Completeness::Incomplete(Pending::Pipe)
+            },
+            Some(Token::Operator(Operator::Conditional(
+                operator @ (ConditionalOperator::And | ConditionalOperator::Or),
+            ))) if self.mode.is_command() => {
+                Completeness::Incomplete(Pending::Conditional(operator))
+            },
+            _ => Completeness::Complete,
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use crate::error::{IdError, LexerError};
+use crate::interpolation::Segments;
+use crate::state::StringState;
+use crate::token::{Literal, Segment};
+use crate::Lexer;
+use rush_core::tracker::Tracker;
+
+impl<S: Iterator<Item = char>> Lexer<'_, S> {
+    /// Reads a double quoted string starting at the opening quote the source is
+    /// currently pointing at, decoding escapes and splitting out `$`
+    /// substitutions.
+    ///
+    /// A string without any substitution is a [`Literal::String`], otherwise it
+    /// is a [`Literal::Interpolated`]. When an escape or a substitution is
+    /// malformed the rest of the string is still consumed and the first error
+    /// is reported, a string running into the end of input is reported as
+    /// [`IdError::UnterminatedString`] pointing at its opening quote.
+    pub(crate) fn read_string(&mut self) -> Result<Literal, LexerError> {
+        let start = self.tracker;
+        self.bump();
+        self.qstate = StringState::DQuote;
+
+        let opening_quote = self.span_from(start);
+
+        let mut string = Segments::default();
+        let mut error = None;
+
+        loop {
+            let Some(&ch) = self.source.peek() else {
+                self.qstate = StringState::default();
+                return Err(LexerError::new(
+                    IdError::UnterminatedString('\"'),
+                    opening_quote,
+                ));
+            };
+
+            match ch {
+                '\"' => {
+                    self.bump();
+                    break;
+                },
+                '\\' => match self.read_escape() {
+                    Ok(ch) => string.buffer.push(ch),
+                    Err(e) => {
+                        error.get_or_insert(e);
+                    },
+                },
+                '$' => self.push_substitution(&mut string, &mut error, self.mode.is_command()),
+                _ => {
+                    self.bump();
+                    string.buffer.push(ch);
+                },
+            }
+        }
+
+        self.qstate = StringState::default();
+        if let Some(e) = error {
+            return Err(e);
+        }
+
+        match &*string.finish() {
+            [Segment::Text(text)] => Ok(Literal::String(text.clone())),
+            segments => Ok(Literal::Interpolated(segments.into())),
+        }
+    }
+
+    /// Returns `true` if the source is pointing at the `r` prefix of a raw
+    /// string, an `r` followed by any number of `#` and a double quote.
+    pub(crate) fn at_raw_string(&mut self) -> bool {
+        if self.source.peek() != Some(&'r') {
+            return false;
+        }
+
+        let hashes = (1..)
+            .take_while(|n| self.source.peek_nth(*n) == Some(&'#'))
+            .count();
+        self.source.peek_nth(hashes + 1) == Some(&'\"')
+    }
+
+    /// Reads a raw string such as `r"C:\path"` or `r#"say "hi""#` starting at
+    /// the `r` prefix the source is currently pointing at.
+    ///
+    /// The contents are taken verbatim, without decoding escapes or expanding
+    /// substitutions, and may span multiple lines. The string is closed by a
+    /// double quote followed by as many `#` as the opening quote was preceded
+    /// by.
+    pub(crate) fn read_raw_string(&mut self) -> Result<Literal, LexerError> {
+        let start = self.tracker;
+        self.bump();
+
+        let mut hashes = 0;
+        while self.bump_if('#') {
+            hashes += 1;
+        }
+
+        self.bump();
+        self.qstate = StringState::DQuote;
+
+        let opening_quote = self.span_from(start);
+        let mut text = String::new();
+
+        loop {
+            match self.bump() {
+                None => {
+                    self.qstate = StringState::default();
+                    return Err(LexerError::new(
+                        IdError::UnterminatedString('\"'),
+                        opening_quote,
+                    ));
+                },
+                Some('\"') if (0..hashes).all(|n| self.source.peek_nth(n) == Some(&'#')) => {
+                    for _ in 0..hashes {
+                        self.bump();
+                    }
+                    break;
+                },
+                Some(ch) => text.push(ch),
+            }
+        }
+
+        self.qstate = StringState::default();
+        Ok(Literal::String(text.into()))
+    }
+
+    /// Reads a triple quoted string starting at the opening `"""` the source is
+    /// currently pointing at.
+    ///
+    /// When the opening or the closing quotes are on a line of their own, the
+    /// line breaks next to them are not part of the string. The indentation
+    /// common to every non-blank line is stripped before escapes are decoded,
+    /// substitutions are not expanded so a `$` is kept as is.
+    pub(crate) fn read_block_string(&mut self) -> Result<Literal, LexerError> {
+        let start = self.tracker;
+        for _ in 0..3 {
+            self.bump();
+        }
+        self.qstate = StringState::DQuote;
+
+        let opening_quote = self.span_from(start);
+        let mut lines = vec![(self.tracker, String::new())];
+
+        loop {
+            if (0..3).all(|n| self.source.peek_nth(n) == Some(&'\"')) {
+                for _ in 0..3 {
+                    self.bump();
+                }
+                break;
+            }
+
+            let Some(ch) = self.bump() else {
+                self.qstate = StringState::default();
+                return Err(LexerError::new(
+                    IdError::UnterminatedString('\"'),
+                    opening_quote,
+                ));
+            };
+
+            let line = &mut lines.last_mut().unwrap().1;
+            match ch {
+                '\n' => lines.push((self.tracker, String::new())),
+                '\\' => {
+                    line.push(ch);
+                    if let Some(next) = self.source.peek().copied().filter(|c| *c != '\n') {
+                        self.bump();
+                        line.push(next);
+                    }
+                },
+                _ => line.push(ch),
+            }
+        }
+
+        self.qstate = StringState::default();
+
+        let is_blank = |line: &str| line.chars().all(|c| c == ' ' || c == '\t');
+        if lines.len() > 1 && is_blank(&lines[0].1) {
+            lines.remove(0);
+        }
+        if lines.len() > 1 && is_blank(&lines[lines.len() - 1].1) {
+            lines.pop();
+        }
+
+        let indent = lines
+            .iter()
+            .filter(|(_, line)| !is_blank(line))
+            .map(|(_, line)| line.len() - line.trim_start_matches([' ', '\t']).len())
+            .min()
+            .unwrap_or(0);
+
+        let mut text = String::new();
+        let mut error = None;
+        for (n, (mut tracker, line)) in lines.into_iter().enumerate() {
+            if n > 0 {
+                text.push('\n');
+            }
+
+            let (indentation, line) = line.split_at(indent.min(line.len()));
+            indentation.chars().for_each(|ch| tracker.advance(ch));
+            decode_escapes(line, tracker, &mut text, &mut error);
+        }
+
+        if let Some(e) = error {
+            return Err(e);
+        }
+
+        Ok(Literal::String(text.into()))
+    }
+
+    /// Reads a single quoted character literal starting at the opening quote
+    /// the source is currently pointing at, the literal must hold exactly one
+    /// (possibly escaped) character and cannot span multiple lines.
+    ///
+    /// Like strings, a literal running into the end of input is reported as
+    /// [`IdError::UnterminatedString`] pointing at its opening quote.
+    pub(crate) fn read_char(&mut self) -> Result<Literal, LexerError> {
+        let start = self.tracker;
+        self.bump();
+        self.qstate = StringState::SQuote;
+
+        let opening_quote = self.span_from(start);
+
+        let mut buffer = String::new();
+        let mut error = None;
+
+        let terminated = loop {
+            match self.source.peek().copied() {
+                None => {
+                    self.qstate = StringState::default();
+                    return Err(LexerError::new(
+                        IdError::UnterminatedString('\''),
+                        opening_quote,
+                    ));
+                },
+                Some('\'') => {
+                    self.bump();
+                    break true;
+                },
+                Some('\\') => match self.read_escape() {
+                    Ok(ch) => buffer.push(ch),
+                    Err(e) => {
+                        error.get_or_insert(e);
+                    },
+                },
+                Some(ch) if ch != '\n' => {
+                    self.bump();
+                    buffer.push(ch);
+                },
+                _ => break false,
+            }
+        };
+
+        self.qstate = StringState::default();
+        let invalid = || {
+            LexerError::new(
+                IdError::InvalidLiteral(buffer.as_str().into()),
+                self.span_from(start),
+            )
+        };
+
+        if !terminated {
+            return Err(invalid());
+        }
+
+        if let Some(e) = error {
+            return Err(e);
+        }
+
+        let mut chars = buffer.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => Ok(Literal::Char(ch)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Decodes the escapes in `line` starting at `tracker` into `text`, keeping
+/// the first error encountered in `error`.
+fn decode_escapes(line: &str, tracker: Tracker, text: &mut String, error: &mut Option<LexerError>) {
+    let mut lexer = Lexer::starting_at(line.chars(), tracker);
+    while let Some(&ch) = lexer.source.peek() {
+        match ch {
+            '\\' => match lexer.read_escape() {
+                Ok(ch) => text.push(ch),
+                Err(e) => {
+                    error.get_or_insert(e);
+                },
+            },
+            _ => {
+                lexer.bump();
+                text.push(ch);
+            },
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use rush_core::tracker::Span;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+use super::Segment;
+
+/// A here-document redirection, `[n]<<DELIMITER` or `[n]<<-DELIMITER`, along
+/// with the body read from the lines following it.
+///
+/// The body is only expanded when the delimiter is not quoted, in which case
+/// it is split into [`Segment`]s like a double quoted string, otherwise it is
+/// a single [`Segment::Text`].
+#[derive(Debug, Clone)]
+pub struct HereDoc {
+    fd: u32,
+    delimiter: Rc<str>,
+    body: Rc<[Segment]>,
+    body_span: Span,
+    expand: bool,
+    strip_tabs: bool,
+}
+
+impl HereDoc {
+    pub(crate) fn new(
+        fd: u32,
+        delimiter: Rc<str>,
+        body: Rc<[Segment]>,
+        body_span: Span,
+        expand: bool,
+        strip_tabs: bool,
+    ) -> Self {
+        Self {
+            fd,
+            delimiter,
+            body,
+            body_span,
+            expand,
+            strip_tabs,
+        }
+    }
+
+    pub fn fd(&self) -> u32 {
+        self.fd
+    }
+
+    pub fn delimiter(&self) -> &str {
+        &self.delimiter
+    }
+
+    pub fn body(&self) -> &[Segment] {
+        &self.body
+    }
+
+    /// Span of the body, from the start of the line following the redirection
+    /// upto the line holding the delimiter.
+    pub fn body_span(&self) -> Span {
+        self.body_span
+    }
+
+    /// Returns `false` if the delimiter was quoted and the body is to be taken
+    /// verbatim.
+    pub fn expand(&self) -> bool {
+        self.expand
+    }
+
+    /// Returns `true` for `<<-`, leading tabs are stripped from the body lines
+    /// and the delimiter line.
+    pub fn strip_tabs(&self) -> bool {
+        self.strip_tabs
+    }
+
+    /// Spells the redirection of the here-document, the body is not part of
+    /// it as it follows on the next lines.
+    pub fn to_source(&self) -> String {
+        let fd = match self.fd {
+            0 => String::new(),
+            fd => fd.to_string(),
+        };
+        let dash = if self.strip_tabs { "-" } else { "" };
+
+        match self.expand {
+            true => format!("{fd}<<{dash}{}", self.delimiter),
+            false => format!("{fd}<<{dash}'{}'", self.delimiter),
+        }
+    }
+}
+
+/// Here-documents compare by value, the position of the body is not taken
+/// into account.
+impl PartialEq for HereDoc {
+    fn eq(&self, other: &Self) -> bool {
+        self.fd == other.fd
+            && self.delimiter == other.delimiter
+            && self.body == other.body
+            && self.expand == other.expand
+            && self.strip_tabs == other.strip_tabs
+    }
+}
+
+impl Hash for HereDoc {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.fd.hash(state);
+        self.delimiter.hash(state);
+        self.body.hash(state);
+        self.expand.hash(state);
+        self.strip_tabs.hash(state);
+    }
+}
@@ -1,7 +1,15 @@
 use crate::error::IdError;
 use std::{fmt::Display, rc::Rc, str::FromStr};
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+use unicode_security::{is_potential_mixed_script_confusable_char, MixedScript};
+use unicode_xid::UnicodeXID;
 
-#[derive(Debug, Clone)]
+/// A name following the Unicode `XID_Start`/`XID_Continue` rules, with `_`
+/// also allowed as the first character.
+///
+/// The name is kept in Normalization Form C, so names that only differ in how
+/// their characters are composed compare equal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Identifier {
     name: Rc<str>,
 }
@@ -10,8 +18,13 @@ impl FromStr for Identifier {
     type Err = IdError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        is_valid_identifier(s)
-            .then_some(Self { name: s.into() })
+        let name: Rc<str> = match is_nfc_quick(s.chars()) {
+            IsNormalized::Yes => s.into(),
+            _ => s.nfc().collect::<String>().into(),
+        };
+
+        is_valid_identifier(&name)
+            .then_some(Self { name })
             .ok_or_else(|| IdError::InvalidLiteral(s.into()))
     }
 }
@@ -26,11 +39,66 @@ impl Identifier {
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    /// Returns `true` if the name mixes characters from different scripts and
+    /// some of them are commonly confused with characters of another script,
+    /// like a Cyrillic `а` among Latin letters.
+    pub fn is_mixed_script_confusable(&self) -> bool {
+        !self.name.is_single_script()
+            && self
+                .name
+                .chars()
+                .any(is_potential_mixed_script_confusable_char)
+    }
+}
+
+#[inline(always)]
+pub(crate) fn is_identifier_start(ch: char) -> bool {
+    ch == '_' || ch.is_xid_start()
+}
+
+#[inline(always)]
+pub(crate) fn is_identifier_continue(ch: char) -> bool {
+    ch.is_xid_continue()
 }
 
 #[inline(always)]
 fn is_valid_identifier(string: &str) -> bool {
-    string.chars().enumerate().all(|(i, ch)| {
-        ch == '_' || ((i == 0 && ch.is_ascii_alphabetic()) || ch.is_ascii_alphanumeric())
-    })
+    let mut chars = string.chars();
+    chars.next().is_some_and(is_identifier_start) && chars.all(is_identifier_continue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unicode_identifiers() {
+        for name in ["größe", "数据", "_x1", "café_2", "ñandú"] {
+            assert_eq!(name.parse::<Identifier>().unwrap().name(), name);
+        }
+
+        for name in ["1x", "a-b", "😀", "x y", ""] {
+            assert!(name.parse::<Identifier>().is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_normalization() {
+        let composed = "caf\u{e9}".parse::<Identifier>().unwrap();
+        let decomposed = "cafe\u{301}".parse::<Identifier>().unwrap();
+
+        assert_eq!(composed, decomposed);
+        assert_eq!(decomposed.name(), "caf\u{e9}");
+    }
+
+    #[test]
+    fn test_mixed_script_confusables() {
+        let ident = |name: &str| name.parse::<Identifier>().unwrap();
+
+        assert!(ident("p\u{430}ssword").is_mixed_script_confusable());
+        assert!(!ident("password").is_mixed_script_confusable());
+        assert!(!ident("пароль").is_mixed_script_confusable());
+        assert!(!ident("データ_1").is_mixed_script_confusable());
+    }
 }
@@ -1,4 +1,4 @@
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Keyword {
     For,
     While,
@@ -7,7 +7,10 @@ pub enum Keyword {
     Const,
     Let,
     Break,
+    Continue,
+    In,
     Return,
+    Fn,
     Struct,
     Enum,
 }
@@ -1,11 +1,157 @@
+use rush_core::tracker::Spanned;
+use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 
-#[derive(Debug, Clone)]
+use super::{is_identifier_continue, CommandSubstitution, Identifier, ParameterExpansion, Token};
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Literal {
     Char(char),
     String(Rc<str>),
+    Interpolated(Rc<[Segment]>),
     Number(isize),
     Float(f64),
     Boolean(bool),
     Nil,
 }
+
+impl Hash for Literal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::Char(ch) => ch.hash(state),
+            Self::String(string) => string.hash(state),
+            Self::Interpolated(segments) => segments.hash(state),
+            Self::Number(number) => number.hash(state),
+            // `0.0` and `-0.0` are equal so they have to hash the same
+            Self::Float(float) => (float + 0.0).to_bits().hash(state),
+            Self::Boolean(boolean) => boolean.hash(state),
+            Self::Nil => {},
+        }
+    }
+}
+
+/// A piece of a double quoted string containing `$name`, `${name:-word}`,
+/// `${ expression }` or `$(command)` substitutions, the segments are to be
+/// concatenated in order once every substitution has been evaluated.
+#[derive(Debug, Clone)]
+pub enum Segment {
+    Text(Rc<str>),
+    Variable(Spanned<Identifier>),
+    Expression(Rc<[Spanned<Token>]>),
+    Command(CommandSubstitution),
+    Parameter(ParameterExpansion),
+}
+
+/// Segments compare by value, the positions of the substitutions are not
+/// taken into account.
+impl PartialEq for Segment {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Text(a), Self::Text(b)) => a == b,
+            (Self::Variable(a), Self::Variable(b)) => a.node == b.node,
+            (Self::Expression(a), Self::Expression(b)) => {
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| a.node == b.node)
+            },
+            (Self::Command(a), Self::Command(b)) => a == b,
+            (Self::Parameter(a), Self::Parameter(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Hash for Segment {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::Text(text) => text.hash(state),
+            Self::Variable(ident) => ident.node.hash(state),
+            Self::Expression(tokens) => tokens.iter().for_each(|t| t.node.hash(state)),
+            Self::Command(command) => command.hash(state),
+            Self::Parameter(parameter) => parameter.hash(state),
+        }
+    }
+}
+
+impl Literal {
+    /// Spells the literal the way it is written in the source, see
+    /// [`Token::to_source`].
+    pub fn to_source(&self) -> String {
+        match self {
+            Self::Char(ch) => format!("'{}'", escape_str(&ch.to_string(), '\'', false)),
+            Self::String(text) => format!("\"{}\"", escape_str(text, '\"', false)),
+            Self::Interpolated(segments) => {
+                let source = segments_to_source(segments, |text, after_variable| {
+                    escape_str(text, '\"', after_variable)
+                });
+                format!("\"{source}\"")
+            },
+            Self::Number(number) => number.to_string(),
+            Self::Float(float) => format!("{float:?}"),
+            Self::Boolean(boolean) => boolean.to_string(),
+            Self::Nil => String::from("nil"),
+        }
+    }
+}
+
+/// Escapes the text of a literal quoted with `quote`, when the text follows a
+/// `$name` substitution a leading identifier character is escaped as well so
+/// that it does not become part of the name.
+fn escape_str(text: &str, quote: char, after_variable: bool) -> String {
+    let mut source = String::new();
+
+    for (n, ch) in text.chars().enumerate() {
+        match ch {
+            '\\' => source.push_str("\\\\"),
+            '\n' => source.push_str("\\n"),
+            '\t' => source.push_str("\\t"),
+            '\r' => source.push_str("\\r"),
+            '\0' => source.push_str("\\0"),
+            '$' if quote == '\"' => source.push_str("\\$"),
+            ch if ch == quote => {
+                source.push('\\');
+                source.push(ch);
+            },
+            ch if ch.is_control() || (n == 0 && after_variable && is_identifier_continue(ch)) => {
+                source.push_str(&format!("\\u{{{:x}}}", ch as u32))
+            },
+            ch => source.push(ch),
+        }
+    }
+
+    source
+}
+
+/// Joins segments back into source text, quoting the text segments with
+/// `text`, which is told if the text directly follows a `$name` substitution.
+pub(crate) fn segments_to_source(
+    segments: &[Segment],
+    text: impl Fn(&str, bool) -> String,
+) -> String {
+    let mut source = String::new();
+
+    for (n, segment) in segments.iter().enumerate() {
+        match segment {
+            Segment::Text(string) => {
+                let after_variable = n > 0 && matches!(segments[n - 1], Segment::Variable(_));
+                source.push_str(&text(string, after_variable));
+            },
+            Segment::Variable(ident) => {
+                source.push('$');
+                source.push_str(ident.node.name());
+            },
+            Segment::Expression(tokens) => {
+                let tokens = tokens
+                    .iter()
+                    .filter(|token| !token.node.is_trivia())
+                    .map(|token| token.node.to_source())
+                    .collect::<Vec<_>>();
+                source.push_str(&format!("${{{}}}", tokens.join(" ")));
+            },
+            Segment::Command(command) => source.push_str(&command.to_source()),
+            Segment::Parameter(parameter) => source.push_str(&parameter.to_source()),
+        }
+    }
+
+    source
+}
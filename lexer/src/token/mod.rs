@@ -1,28 +1,70 @@
 use crate::error::IdError;
+use crate::number::parse_number;
 use phf::{phf_map, Map};
 use std::{fmt::Display, str::FromStr, write};
 
 pub use delimitter::Delimitter;
+pub use heredoc::HereDoc;
 pub use identifier::Identifier;
+pub(crate) use identifier::{is_identifier_continue, is_identifier_start};
 pub use keyword::Keyword;
-pub use literal::Literal;
+pub use literal::{Literal, Segment};
 pub use operator::*;
+pub use parameter::{ParameterExpansion, ParameterOperation};
+pub use substitution::CommandSubstitution;
+pub use trivia::Trivia;
+pub use word::Word;
+
+pub(crate) use literal::segments_to_source;
 
 pub mod delimitter;
+pub mod heredoc;
 pub mod identifier;
 pub mod keyword;
 pub mod literal;
 pub mod operator;
+pub mod parameter;
+pub mod substitution;
+pub mod trivia;
+pub mod word;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub enum Token {
     Operator(Operator),
     Delimitter(Delimitter),
     Literal(Literal),
     Keyword(Keyword),
     Identifier(Identifier),
+    Word(Word),
+    Shell(ShellOperator),
+    HereDoc(HereDoc),
+    Substitution(CommandSubstitution),
+    Parameter(ParameterExpansion),
+    Trivia(Trivia),
+    Error(IdError),
+}
+
+/// Payload free counterpart of [`Token`], for matching on the kind of a token
+/// without caring about its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TokenKind {
+    Operator,
+    Delimitter,
+    Literal,
+    Keyword,
+    Identifier,
+    Word,
+    Shell,
+    HereDoc,
+    Substitution,
+    Parameter,
+    Trivia,
+    Error,
 }
 
+/// Length of the longest operator or delimitter in [`TOKENS`].
+pub(crate) const MAX_SYMBOL_LEN: usize = 3;
+
 pub(crate) const TOKENS: Map<&'static str, Token> = phf_map! {
     "`"  => Token::Delimitter(Delimitter::BackTick),
     "@"  => Token::Delimitter(Delimitter::At),
@@ -47,7 +89,10 @@ pub(crate) const TOKENS: Map<&'static str, Token> = phf_map! {
     "const"  => Token::Keyword(Keyword::Const),
     "let"    => Token::Keyword(Keyword::Let),
     "break"  => Token::Keyword(Keyword::Break),
+    "continue" => Token::Keyword(Keyword::Continue),
+    "in"     => Token::Keyword(Keyword::In),
     "return" => Token::Keyword(Keyword::Return),
+    "fn"     => Token::Keyword(Keyword::Fn),
     "struct" => Token::Keyword(Keyword::Struct),
     "enum"   => Token::Keyword(Keyword::Enum),
 
@@ -93,6 +138,22 @@ pub(crate) const TOKENS: Map<&'static str, Token> = phf_map! {
     "false" => Token::Literal(Literal::Boolean(false)),
 };
 
+/// Returns the text a token is spelled with in the [`TOKENS`] table.
+pub(crate) fn spelling(token: &Token) -> Option<&'static str> {
+    TOKENS
+        .entries()
+        .find(|(_, entry)| *entry == token)
+        .map(|(text, _)| *text)
+}
+
+/// Returns `true` if the character is a single character [`Delimitter`].
+pub(crate) fn is_delimitter(ch: char) -> bool {
+    matches!(
+        TOKENS.get(ch.encode_utf8(&mut [0; 4])),
+        Some(Token::Delimitter(_))
+    )
+}
+
 impl FromStr for Token {
     type Err = IdError;
 
@@ -101,24 +162,109 @@ impl FromStr for Token {
             Ok(kind.clone())
         } else if let Ok(ident) = s.parse() {
             Ok(Self::Identifier(ident))
-        } else if let Ok(num) = s.parse::<isize>() {
-            Ok(Self::Literal(Literal::Number(num)))
-        } else if let Ok(f) = s.parse::<f64>() {
-            Ok(Self::Literal(Literal::Float(f)))
+        } else if s.starts_with(|c: char| c.is_ascii_digit()) {
+            parse_number(s).map(Self::Literal)
         } else {
             Err(IdError::UnidentifiedToken(s.into()))
         }
     }
 }
 
-impl Display for Token {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl Token {
+    pub fn kind(&self) -> TokenKind {
+        match self {
+            Self::Operator(_) => TokenKind::Operator,
+            Self::Delimitter(_) => TokenKind::Delimitter,
+            Self::Literal(_) => TokenKind::Literal,
+            Self::Keyword(_) => TokenKind::Keyword,
+            Self::Identifier(_) => TokenKind::Identifier,
+            Self::Word(_) => TokenKind::Word,
+            Self::Shell(_) => TokenKind::Shell,
+            Self::HereDoc(_) => TokenKind::HereDoc,
+            Self::Substitution(_) => TokenKind::Substitution,
+            Self::Parameter(_) => TokenKind::Parameter,
+            Self::Trivia(_) => TokenKind::Trivia,
+            Self::Error(_) => TokenKind::Error,
+        }
+    }
+
+    /// Spells the token the way it is written in the source, lexing the text
+    /// again gives back an equal token.
+    ///
+    /// Here-documents only give back the redirection, their bodies are on the
+    /// lines following it. Errors give back the offending text when it is
+    /// known and nothing otherwise.
+    ///
+    /// Example
+    /// ```
+    /// use lexer::Lexer;
+    ///
+    /// let string = String::from("let x = \"a\\tb\" + 0x10 ..= y;");
+    /// let spelled = Lexer::new(Box::new(string.chars()))
+    ///     .map(|t| t.unwrap().node.to_source())
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(spelled, ["let", "x", "=", "\"a\\tb\"", "+", "16", "..=", "y", ";"]);
+    /// ```
+    pub fn to_source(&self) -> String {
+        if let Some(text) = spelling(self) {
+            return text.to_owned();
+        }
+
         match self {
-            Self::Identifier(ident) => write!(f, "{}", ident),
-            Self::Literal(lit) => write!(f, "{:?}", lit),
-            Self::Operator(op) => write!(f, "{:?}", op),
-            Self::Keyword(kw) => write!(f, "{:?}", kw),
-            Self::Delimitter(delm) => write!(f, "{:?}", delm),
+            Self::Identifier(ident) => ident.name().to_owned(),
+            Self::Literal(literal) => literal.to_source(),
+            Self::Word(word) => word.to_source(),
+            Self::Shell(operator) => operator.to_source(),
+            Self::HereDoc(heredoc) => heredoc.to_source(),
+            Self::Substitution(command) => command.to_source(),
+            Self::Parameter(parameter) => parameter.to_source(),
+            Self::Trivia(trivia) => trivia.to_source(),
+            Self::Error(
+                IdError::UnidentifiedToken(text)
+                | IdError::InvalidLiteral(text)
+                | IdError::IntegerOverflow(text)
+                | IdError::InvalidEscape(text)
+                | IdError::InvalidInterpolation(text),
+            ) => text.to_string(),
+            _ => String::new(),
         }
     }
+
+    /// Returns `true` if the token is an assignment operator, see
+    /// [`Operator::is_assignment`].
+    pub fn is_assignment(&self) -> bool {
+        matches!(self, Self::Operator(op) if op.is_assignment())
+    }
+
+    /// Returns `true` if the token is a comparison operator, see
+    /// [`Operator::is_comparison`].
+    pub fn is_comparison(&self) -> bool {
+        matches!(self, Self::Operator(op) if op.is_comparison())
+    }
+
+    /// Returns `true` if the token is an operator that can prefix an operand,
+    /// see [`Operator::is_unary`].
+    pub fn is_unary(&self) -> bool {
+        matches!(self, Self::Operator(op) if op.is_unary())
+    }
+
+    /// Binding power of the token as a binary operator, see
+    /// [`Operator::binary_precedence`].
+    pub fn binary_precedence(&self) -> Option<u8> {
+        match self {
+            Self::Operator(op) => op.binary_precedence(),
+            _ => None,
+        }
+    }
+
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, Self::Trivia(_))
+    }
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_source())
+    }
 }
@@ -1,4 +1,4 @@
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Operator {
     Bitwise(BitwiseOperator),
     Arithmetic(ArithmeticOperator),
@@ -10,7 +10,7 @@ pub enum Operator {
     Misc(MiscOperator),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum ArithmeticOperator {
     Divide,
     Multiply,
@@ -20,7 +20,7 @@ pub enum ArithmeticOperator {
     DecrAssign,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum BitwiseOperator {
     BitWiseNot,
     LeftShift,
@@ -33,14 +33,14 @@ pub enum BitwiseOperator {
     XorAssign,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum ConditionalOperator {
     And,
     Or,
     Not,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum RelationalOperator {
     GreaterThan,
     GreaterThanOrEqual,
@@ -50,16 +50,150 @@ pub enum RelationalOperator {
     NotEqual,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum RangeOperator {
     InclusiveRange,
     ExclusiveRange,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum MiscOperator {
     Assign,
     FatArrow,
     ThinArrow,
     ScopeResolution,
 }
+
+impl Operator {
+    /// Returns `true` for `=` and the compound assignments like `+=`.
+    pub fn is_assignment(&self) -> bool {
+        matches!(
+            self,
+            Self::Misc(MiscOperator::Assign)
+                | Self::Arithmetic(ArithmeticOperator::IncrAssign | ArithmeticOperator::DecrAssign)
+                | Self::Bitwise(
+                    BitwiseOperator::BitWiseAndAssign
+                        | BitwiseOperator::BitWiseOrAssign
+                        | BitwiseOperator::XorAssign
+                )
+        )
+    }
+
+    /// Returns `true` for the [`RelationalOperator`]s.
+    pub fn is_comparison(&self) -> bool {
+        matches!(self, Self::Relational(_))
+    }
+
+    /// Returns `true` for the operators that can prefix an operand, `!`, `~`
+    /// and `-`.
+    pub fn is_unary(&self) -> bool {
+        matches!(
+            self,
+            Self::Conditional(ConditionalOperator::Not)
+                | Self::Bitwise(BitwiseOperator::BitWiseNot)
+                | Self::Arithmetic(ArithmeticOperator::Minus)
+        )
+    }
+
+    /// Binding power of the operator between two operands, higher binds
+    /// tighter. All binary operators are left associative, assignments and
+    /// operators that cannot appear between two operands have none.
+    ///
+    /// | Precedence | Operators                      |
+    /// |------------|--------------------------------|
+    /// | 10         | `*` `/`                        |
+    /// | 9          | `+` `-`                        |
+    /// | 8          | `<<` `>>`                      |
+    /// | 7          | `&`                            |
+    /// | 6          | `^`                            |
+    /// | 5          | `\|`                           |
+    /// | 4          | `==` `!=` `<` `<=` `>` `>=`    |
+    /// | 3          | `&&`                           |
+    /// | 2          | `\|\|`                         |
+    /// | 1          | `..` `..=`                     |
+    pub fn binary_precedence(&self) -> Option<u8> {
+        use ArithmeticOperator::*;
+        use BitwiseOperator::*;
+
+        let precedence = match self {
+            Self::Arithmetic(Multiply | Divide) => 10,
+            Self::Arithmetic(Plus | Minus) => 9,
+            Self::Bitwise(LeftShift | RightShift) => 8,
+            Self::Bitwise(BitWiseAnd) => 7,
+            Self::Bitwise(Xor) => 6,
+            Self::Bitwise(BitWiseOr) => 5,
+            Self::Relational(_) => 4,
+            Self::Conditional(ConditionalOperator::And) => 3,
+            Self::Conditional(ConditionalOperator::Or) => 2,
+            Self::Range(_) => 1,
+            _ => return None,
+        };
+
+        Some(precedence)
+    }
+}
+
+/// Operators of the command language, only lexed in
+/// [`Mode::Command`](crate::Mode::Command).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ShellOperator {
+    Pipe,
+    Background,
+    /// An unquoted line break, ending the command before it like a `;`, along
+    /// with any here-document bodies that follow the line it ends.
+    Newline,
+    Redirect(Redirect),
+}
+
+/// A redirection with the file descriptor it applies to, descriptors left
+/// out in the source default to `0` for input and `1` for output.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Redirect {
+    /// `[n]<`
+    Input { fd: u32 },
+    /// `[n]>` and `[n]>>`
+    Output { fd: u32, append: bool },
+    /// `[n]>&m` and `[n]<&m`, `input` for the latter
+    Duplicate { fd: u32, target: u32, input: bool },
+    /// `[n]>&-` and `[n]<&-`, closing the file descriptor
+    Close { fd: u32, input: bool },
+    /// `&>`, `&>>` and `>&`, standard output and error to the same target
+    OutputAndError { append: bool },
+    /// `[n]<<<`
+    HereString { fd: u32 },
+}
+
+impl ShellOperator {
+    /// Spells the operator, file descriptors matching the default of the
+    /// redirection are left out.
+    pub fn to_source(&self) -> String {
+        let prefix = |fd: u32, default: u32| match fd == default {
+            true => String::new(),
+            false => fd.to_string(),
+        };
+
+        match self {
+            Self::Pipe => String::from("|"),
+            Self::Background => String::from("&"),
+            Self::Newline => String::from("\n"),
+            Self::Redirect(Redirect::Input { fd }) => format!("{}<", prefix(*fd, 0)),
+            Self::Redirect(Redirect::Output { fd, append }) => {
+                let arrows = if *append { ">>" } else { ">" };
+                format!("{}{arrows}", prefix(*fd, 1))
+            },
+            Self::Redirect(Redirect::Duplicate { fd, target, input }) => match input {
+                true => format!("{}<&{target}", prefix(*fd, 0)),
+                false => format!("{}>&{target}", prefix(*fd, 1)),
+            },
+            Self::Redirect(Redirect::Close { fd, input }) => match input {
+                true => format!("{}<&-", prefix(*fd, 0)),
+                false => format!("{}>&-", prefix(*fd, 1)),
+            },
+            Self::Redirect(Redirect::OutputAndError { append }) => {
+                let arrows = if *append { "&>>" } else { "&>" };
+                arrows.to_owned()
+            },
+            Self::Redirect(Redirect::HereString { fd }) => format!("{}<<<", prefix(*fd, 0)),
+        }
+    }
+}
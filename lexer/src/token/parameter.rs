@@ -0,0 +1,168 @@
+use std::rc::Rc;
+
+use super::{segments_to_source, Identifier, Segment};
+
+/// A `${name<operation>}` shell parameter expansion, like `${HOME:-/root}` or
+/// `${#PATH}`.
+///
+/// The words of the operation are split into [`Segment`]s so that they can
+/// contain substitutions of their own. In patterns, the glob characters that
+/// were quoted or escaped in the source are kept escaped with a backslash, a
+/// literal backslash is kept as `\\`.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct ParameterExpansion {
+    name: Identifier,
+    operation: ParameterOperation,
+}
+
+/// What a [`ParameterExpansion`] does with the value of its parameter.
+///
+/// For the forms with a `null` flag, the flag is set by the `:` variants,
+/// `${x:-y}` rather than `${x-y}`, which treat a parameter set to the empty
+/// string as if it were unset.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub enum ParameterOperation {
+    /// `${#name}`, the number of characters in the value.
+    Length,
+
+    /// `${name:-word}`, the value or `word` if the parameter is unset.
+    Default { word: Rc<[Segment]>, null: bool },
+
+    /// `${name:=word}`, like [`Default`](Self::Default) but `word` is also
+    /// assigned to the parameter.
+    Assign { word: Rc<[Segment]>, null: bool },
+
+    /// `${name:?word}`, the value or an error with `word` as its message if the
+    /// parameter is unset.
+    Error { word: Rc<[Segment]>, null: bool },
+
+    /// `${name:+word}`, `word` if the parameter is set and nothing otherwise.
+    Alternative { word: Rc<[Segment]>, null: bool },
+
+    /// `${name#pattern}`, the value without the shortest prefix matching
+    /// `pattern`, or the longest one for `##`.
+    RemovePrefix {
+        pattern: Rc<[Segment]>,
+        longest: bool,
+    },
+
+    /// `${name%pattern}`, the value without the shortest suffix matching
+    /// `pattern`, or the longest one for `%%`.
+    RemoveSuffix {
+        pattern: Rc<[Segment]>,
+        longest: bool,
+    },
+
+    /// `${name/pattern/replacement}`, the value with the first match of
+    /// `pattern` replaced, or every match for `//`.
+    Replace {
+        pattern: Rc<[Segment]>,
+        replacement: Rc<[Segment]>,
+        all: bool,
+    },
+
+    /// `${name^}`, the value with its first character in upper case, or every
+    /// character for `^^`.
+    Uppercase { all: bool },
+
+    /// `${name,}`, the value with its first character in lower case, or every
+    /// character for `,,`.
+    Lowercase { all: bool },
+}
+
+impl ParameterExpansion {
+    pub(crate) fn new(name: Identifier, operation: ParameterOperation) -> Self {
+        Self { name, operation }
+    }
+
+    pub fn name(&self) -> &Identifier {
+        &self.name
+    }
+
+    pub fn operation(&self) -> &ParameterOperation {
+        &self.operation
+    }
+
+    /// Spells the expansion the way it is written in the source, see
+    /// [`Token::to_source`](super::Token::to_source).
+    pub fn to_source(&self) -> String {
+        use ParameterOperation::*;
+
+        let name = self.name.name();
+        let colon = |null: bool| if null { ":" } else { "" };
+        let double = |op: &str, twice: bool| if twice { op.repeat(2) } else { op.to_owned() };
+
+        let operation = match &self.operation {
+            Length => return format!("${{#{name}}}"),
+            Default { word, null } => format!("{}-{}", colon(*null), word_to_source(word)),
+            Assign { word, null } => format!("{}={}", colon(*null), word_to_source(word)),
+            Error { word, null } => format!("{}?{}", colon(*null), word_to_source(word)),
+            Alternative { word, null } => format!("{}+{}", colon(*null), word_to_source(word)),
+            RemovePrefix { pattern, longest } => affix_to_source("#", pattern, *longest),
+            RemoveSuffix { pattern, longest } => affix_to_source("%", pattern, *longest),
+            Replace {
+                pattern,
+                replacement,
+                all,
+            } => format!(
+                "{}{}/{}",
+                double("/", *all),
+                pattern_to_source(pattern, true),
+                word_to_source(replacement)
+            ),
+            Uppercase { all } => double("^", *all),
+            Lowercase { all } => double(",", *all),
+        };
+
+        format!("${{{name}{operation}}}")
+    }
+}
+
+/// Spells a `#` or `%` operation, a pattern starting with the operator itself
+/// is escaped so it is not read as the doubled operator.
+fn affix_to_source(op: &str, pattern: &[Segment], longest: bool) -> String {
+    let pattern = pattern_to_source(pattern, false);
+    match (longest, pattern.starts_with(op)) {
+        (true, _) => format!("{op}{op}{pattern}"),
+        (false, true) => format!("{op}\\{pattern}"),
+        (false, false) => format!("{op}{pattern}"),
+    }
+}
+
+/// Spells the word of an operation, escaping the characters that would end
+/// the expansion or start a substitution.
+fn word_to_source(word: &[Segment]) -> String {
+    segments_to_source(word, |text, after_variable| {
+        escape_word(text, after_variable, |ch| {
+            matches!(ch, '\\' | '}' | '$' | '`' | '\"' | '\'')
+        })
+    })
+}
+
+/// Spells a pattern, its backslashes already escape the character following
+/// them and are kept as they are.
+fn pattern_to_source(pattern: &[Segment], slash: bool) -> String {
+    segments_to_source(pattern, |text, after_variable| {
+        let mut escaped = false;
+        escape_word(text, after_variable, |ch| {
+            let escape =
+                !escaped && (matches!(ch, '}' | '$' | '`' | '\"' | '\'') || (slash && ch == '/'));
+            escaped = !escaped && ch == '\\';
+            escape
+        })
+    })
+}
+
+fn escape_word(text: &str, after_variable: bool, mut escape: impl FnMut(char) -> bool) -> String {
+    let mut source = String::new();
+
+    for (n, ch) in text.chars().enumerate() {
+        let leading = n == 0 && after_variable && super::is_identifier_continue(ch);
+        if escape(ch) || leading {
+            source.push('\\');
+        }
+        source.push(ch);
+    }
+
+    source
+}
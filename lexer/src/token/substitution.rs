@@ -0,0 +1,78 @@
+use rush_core::tracker::Span;
+use std::hash::{Hash, Hasher};
+use std::{rc::Rc, str::Chars};
+
+use crate::{Lexer, Mode};
+
+/// A command substitution, `$(command)` or `` `command` ``, to be replaced by
+/// the captured standard output of the command at runtime.
+///
+/// The source of the command is kept as is so it can be lexed again, for
+/// backticks it is the source after removing the backslashes escaping `` ` ``,
+/// `\` and `$`.
+#[derive(Debug, Clone)]
+pub struct CommandSubstitution {
+    source: Rc<str>,
+    source_span: Span,
+}
+
+impl CommandSubstitution {
+    pub(crate) fn new(source: Rc<str>, source_span: Span) -> Self {
+        Self {
+            source,
+            source_span,
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Spells the substitution in its `$(command)` form.
+    pub fn to_source(&self) -> String {
+        format!("$({})", self.source)
+    }
+
+    /// Span of the command within the enclosing source, excluding the
+    /// surrounding `$(`, `)` or backticks.
+    pub fn source_span(&self) -> Span {
+        self.source_span
+    }
+
+    /// Returns a [`Mode::Command`] lexer over the command, reporting
+    /// positions relative to the enclosing source.
+    ///
+    /// Example
+    /// ```
+    /// use lexer::Lexer;
+    /// use lexer::token::Token;
+    ///
+    /// let string = String::from("$(ls $(pwd))");
+    /// let token = Lexer::new(Box::new(string.chars())).next().unwrap().unwrap();
+    ///
+    /// let Token::Substitution(command) = token.node else { panic!() };
+    /// assert_eq!(command.source(), "ls $(pwd)");
+    ///
+    /// let inner = command.lexer().map(|t| t.unwrap()).collect::<Vec<_>>();
+    /// assert_eq!(inner.len(), 2);
+    /// assert_eq!(inner[1].span.source_text(&string), Some("$(pwd)"));
+    /// ```
+    pub fn lexer(&self) -> Lexer<'_, Chars<'_>> {
+        Lexer::starting_at(self.source.chars(), self.source_span.start()).with_mode(Mode::Command)
+    }
+}
+
+/// Substitutions compare by their source, wherever they are found.
+impl PartialEq for CommandSubstitution {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for CommandSubstitution {}
+
+impl Hash for CommandSubstitution {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.source.hash(state);
+    }
+}
@@ -0,0 +1,46 @@
+use std::rc::Rc;
+
+/// Source text between tokens that carries no meaning for the grammar, only
+/// produced by a [`Lexer`](crate::Lexer) with trivia enabled.
+///
+/// Comments hold their text without the leading `#` or `##`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Trivia {
+    /// A run of whitespace other than line breaks.
+    Whitespace(Rc<str>),
+
+    /// A single line break outside of [`Mode::Command`](crate::Mode::Command),
+    /// where it is a [`ShellOperator::Newline`](super::ShellOperator::Newline)
+    /// instead.
+    Newline,
+
+    /// A `#` comment upto the end of the line.
+    Comment(Rc<str>),
+
+    /// A `##` documentation comment upto the end of the line.
+    DocComment(Rc<str>),
+
+    /// A byte order mark at the very start of the source.
+    ByteOrderMark,
+}
+
+impl Trivia {
+    /// Returns `true` if the trivia is a [`Comment`] or a [`DocComment`].
+    ///
+    /// [`Comment`]: Trivia::Comment
+    /// [`DocComment`]: Trivia::DocComment
+    #[must_use]
+    pub fn is_comment(&self) -> bool {
+        matches!(self, Self::Comment(_) | Self::DocComment(_))
+    }
+
+    pub fn to_source(&self) -> String {
+        match self {
+            Self::Whitespace(whitespace) => whitespace.to_string(),
+            Self::Newline => String::from("\n"),
+            Self::Comment(comment) => format!("#{comment}"),
+            Self::DocComment(comment) => format!("##{comment}"),
+            Self::ByteOrderMark => String::from("\u{feff}"),
+        }
+    }
+}
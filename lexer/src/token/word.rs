@@ -0,0 +1,93 @@
+use std::rc::Rc;
+
+use super::{is_identifier_continue, segments_to_source, Segment};
+
+/// A bare shell word lexed in [`Mode::Command`](crate::Mode::Command).
+///
+/// Quoting and backslash escapes are already resolved, the word is split into
+/// the same [`Segment`]s as an interpolated string so that `$name` and
+/// `${ expression }` substitutions can be expanded at runtime.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Word {
+    segments: Rc<[Segment]>,
+    glob: bool,
+    assignment: bool,
+}
+
+impl Word {
+    pub(crate) fn new(segments: Rc<[Segment]>, glob: bool, assignment: bool) -> Self {
+        Self {
+            segments,
+            glob,
+            assignment,
+        }
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Returns the text of the word if it does not contain any substitution.
+    pub fn text(&self) -> Option<&str> {
+        match &*self.segments {
+            [Segment::Text(text)] => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the word contains an unquoted `*`, `?` or `[`.
+    pub fn is_glob(&self) -> bool {
+        self.glob
+    }
+
+    /// Returns `true` if the word starts with a `-`, like `-la` or `--help`.
+    pub fn is_flag(&self) -> bool {
+        matches!(self.segments.first(), Some(Segment::Text(text)) if text.starts_with('-'))
+    }
+
+    /// Returns the name being assigned to if the word is of the form
+    /// `key=value` with an unquoted, valid identifier as the key.
+    pub fn assignment_name(&self) -> Option<&str> {
+        match self.segments.first() {
+            Some(Segment::Text(text)) if self.assignment => text.split_once('=').map(|(k, _)| k),
+            _ => None,
+        }
+    }
+}
+
+impl Word {
+    /// Spells the word the way it could be written in the source, escaping
+    /// the characters that would otherwise change its meaning, see
+    /// [`Token::to_source`](super::Token::to_source).
+    pub fn to_source(&self) -> String {
+        segments_to_source(&self.segments, |text, after_variable| {
+            if text.is_empty() {
+                return String::from("''");
+            }
+
+            if text.contains('\n') {
+                return format!("'{}'", text.replace('\'', "'\\''"));
+            }
+
+            let mut source = String::new();
+            for (n, ch) in text.chars().enumerate() {
+                let escape = ch.is_whitespace()
+                    || matches!(
+                        ch,
+                        ';' | '|' | '&' | '<' | '>' | '(' | ')' | '\'' | '\"' | '`' | '\\' | '$'
+                    )
+                    || (n == 0 && ch == '#')
+                    || (!self.glob && matches!(ch, '*' | '?' | '['))
+                    || (!self.assignment && ch == '=')
+                    || (n == 0 && after_variable && is_identifier_continue(ch));
+
+                if escape {
+                    source.push('\\');
+                }
+                source.push(ch);
+            }
+
+            source
+        })
+    }
+}
@@ -1,4 +1,4 @@
-use rush_core::tracker::Tracker;
+use rush_core::tracker::Span;
 use std::rc::Rc;
 
 use lexer::error::LexerError;
@@ -6,10 +6,41 @@ use thiserror::Error;
 
 #[derive(Error)]
 #[derive(Debug)]
-#[error("Error: {message} at: {tracker}")]
+#[error("Error: {message} at: {span}")]
 pub struct ParseError {
     message: Rc<str>,
-    tracker: Tracker,
+    span: Span,
+}
+
+impl ParseError {
+    pub fn new(message: impl Into<Rc<str>>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// Failures of a shell parameter expansion, see [`crate::expansion`].
+#[derive(Error)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum ExpansionError {
+    /// A `${name:?message}` expansion of an unset parameter.
+    #[error("{name}: {message}")]
+    Unset { name: Rc<str>, message: Rc<str> },
+
+    /// A word containing a substitution that can only be expanded by running
+    /// it, like `${ expression }` or `$(command)`.
+    #[error("Cannot expand '{0}' here")]
+    Unsupported(Rc<str>),
 }
 
 #[derive(Error)]
@@ -20,4 +51,7 @@ pub enum Error {
 
     #[error("{0}")]
     Parser(#[from] ParseError),
+
+    #[error("{0}")]
+    Expansion(#[from] ExpansionError),
 }
@@ -0,0 +1,410 @@
+//! Evaluation of shell parameter expansions, `${name:-word}` and friends,
+//! following bash.
+
+use lexer::token::{ParameterExpansion, ParameterOperation, Segment};
+use std::collections::HashMap;
+
+use crate::error::ExpansionError;
+
+/// The shell variables an expansion reads and assigns to. A variable missing
+/// from the store is unset, which is not the same as being set to the empty
+/// string.
+pub trait Variables {
+    fn get(&self, name: &str) -> Option<&str>;
+    fn set(&mut self, name: &str, value: String);
+}
+
+impl Variables for HashMap<String, String> {
+    fn get(&self, name: &str) -> Option<&str> {
+        HashMap::get(self, name).map(String::as_str)
+    }
+
+    fn set(&mut self, name: &str, value: String) {
+        self.insert(name.to_owned(), value);
+    }
+}
+
+/// Expands `parameter` against `variables`.
+///
+/// Words are only expanded when their value is needed, so `${x:-$y}` does not
+/// look at `y` when `x` is set. Patterns use glob syntax, `*`, `?` and
+/// bracket expressions, and the replaced match is the longest one starting at
+/// the leftmost position that matches.
+///
+/// Example
+/// ```
+/// use lexer::Lexer;
+/// use lexer::token::Token;
+/// use parser::expansion::expand;
+/// use std::collections::HashMap;
+///
+/// let token = Lexer::from_source("${name:=world}").next().unwrap().unwrap();
+/// let Token::Parameter(parameter) = token.node else { panic!() };
+///
+/// let mut variables = HashMap::new();
+/// assert_eq!(expand(&parameter, &mut variables).unwrap(), "world");
+/// assert_eq!(variables["name"], "world");
+/// ```
+pub fn expand(
+    parameter: &ParameterExpansion,
+    variables: &mut impl Variables,
+) -> Result<String, ExpansionError> {
+    use ParameterOperation::*;
+
+    let name = parameter.name().name();
+    let value = variables.get(name).map(str::to_owned);
+    let unset = |null: bool| value.as_deref().is_none_or(|v| null && v.is_empty());
+
+    let expanded = match parameter.operation() {
+        Length => value.unwrap_or_default().chars().count().to_string(),
+        Default { word, null } => match unset(*null) {
+            true => expand_word(word, variables)?,
+            false => value.unwrap_or_default(),
+        },
+        Assign { word, null } => match unset(*null) {
+            true => {
+                let word = expand_word(word, variables)?;
+                variables.set(name, word.clone());
+                word
+            },
+            false => value.unwrap_or_default(),
+        },
+        Error { word, null } => match unset(*null) {
+            true => {
+                let mut message = expand_word(word, variables)?;
+                if message.is_empty() {
+                    message = match null {
+                        true => String::from("parameter null or not set"),
+                        false => String::from("parameter not set"),
+                    };
+                }
+
+                return Err(ExpansionError::Unset {
+                    name: name.into(),
+                    message: message.into(),
+                });
+            },
+            false => value.unwrap_or_default(),
+        },
+        Alternative { word, null } => match unset(*null) {
+            true => String::new(),
+            false => expand_word(word, variables)?,
+        },
+        RemovePrefix { pattern, longest } => {
+            let value = value.unwrap_or_default().chars().collect::<Vec<_>>();
+            let pattern = expand_word(pattern, variables)?.chars().collect::<Vec<_>>();
+
+            let mut ends = 0..=value.len();
+            let end = match longest {
+                true => ends.rfind(|&end| glob_match(&pattern, &value[..end])),
+                false => ends.find(|&end| glob_match(&pattern, &value[..end])),
+            };
+
+            value[end.unwrap_or(0)..].iter().collect()
+        },
+        RemoveSuffix { pattern, longest } => {
+            let value = value.unwrap_or_default().chars().collect::<Vec<_>>();
+            let pattern = expand_word(pattern, variables)?.chars().collect::<Vec<_>>();
+
+            let mut starts = 0..=value.len();
+            let start = match longest {
+                true => starts.find(|&start| glob_match(&pattern, &value[start..])),
+                false => starts.rfind(|&start| glob_match(&pattern, &value[start..])),
+            };
+
+            value[..start.unwrap_or(value.len())].iter().collect()
+        },
+        Replace {
+            pattern,
+            replacement,
+            all,
+        } => {
+            let value = value.unwrap_or_default();
+            let pattern = expand_word(pattern, variables)?.chars().collect::<Vec<_>>();
+            if pattern.is_empty() {
+                return Ok(value);
+            }
+
+            let replacement = expand_word(replacement, variables)?;
+            replace(&value, &pattern, &replacement, *all)
+        },
+        Uppercase { all } => change_case(&value.unwrap_or_default(), *all, char::to_uppercase),
+        Lowercase { all } => change_case(&value.unwrap_or_default(), *all, char::to_lowercase),
+    };
+
+    Ok(expanded)
+}
+
+/// Expands the segments of a word, unset variables expand to nothing.
+fn expand_word(
+    segments: &[Segment],
+    variables: &mut impl Variables,
+) -> Result<String, ExpansionError> {
+    let mut word = String::new();
+
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => word.push_str(text),
+            Segment::Variable(ident) => {
+                word.push_str(variables.get(ident.node.name()).unwrap_or(""))
+            },
+            Segment::Parameter(parameter) => word.push_str(&expand(parameter, variables)?),
+            Segment::Command(command) => {
+                return Err(ExpansionError::Unsupported(command.to_source().into()))
+            },
+            Segment::Expression(tokens) => {
+                let tokens = tokens
+                    .iter()
+                    .map(|token| token.node.to_source())
+                    .collect::<Vec<_>>();
+                let source = format!("${{{}}}", tokens.join(" "));
+                return Err(ExpansionError::Unsupported(source.into()));
+            },
+        }
+    }
+
+    Ok(word)
+}
+
+/// Replaces the first match of `pattern`, or every match when `all` is set,
+/// empty matches are skipped.
+fn replace(value: &str, pattern: &[char], replacement: &str, all: bool) -> String {
+    let value = value.chars().collect::<Vec<_>>();
+    let mut replaced = String::new();
+    let mut start = 0;
+
+    while start < value.len() {
+        let end = (start + 1..=value.len())
+            .rev()
+            .find(|&end| glob_match(pattern, &value[start..end]));
+
+        match end {
+            Some(end) => {
+                replaced.push_str(replacement);
+                start = end;
+
+                if !all {
+                    break;
+                }
+            },
+            None => {
+                replaced.push(value[start]);
+                start += 1;
+            },
+        }
+    }
+
+    replaced.extend(&value[start..]);
+    replaced
+}
+
+fn change_case<I: Iterator<Item = char>>(value: &str, all: bool, case: fn(char) -> I) -> String {
+    let mut chars = value.chars();
+    match all {
+        true => chars.flat_map(case).collect(),
+        false => chars
+            .next()
+            .map(|first| case(first).chain(chars).collect())
+            .unwrap_or_default(),
+    }
+}
+
+/// Matches the whole of `text` against a glob pattern, where a backslash makes
+/// the character following it match only itself.
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', rest @ ..] => (0..=text.len()).any(|n| glob_match(rest, &text[n..])),
+        ['?', rest @ ..] => !text.is_empty() && glob_match(rest, &text[1..]),
+        ['[', rest @ ..] if bracket_end(rest).is_some() => {
+            let end = bracket_end(rest).unwrap_or_default();
+            match text.split_first() {
+                Some((ch, text)) => {
+                    bracket_match(&rest[..end], *ch) && glob_match(&rest[end + 1..], text)
+                },
+                None => false,
+            }
+        },
+        ['\\', ch, rest @ ..] | [ch, rest @ ..] => {
+            text.first() == Some(ch) && glob_match(rest, &text[1..])
+        },
+    }
+}
+
+/// Index of the `]` closing a bracket expression whose content starts at
+/// `pattern`, a `]` right after the opening `[` or its negation is taken as
+/// part of the set.
+fn bracket_end(pattern: &[char]) -> Option<usize> {
+    let mut n = usize::from(matches!(pattern.first(), Some('!' | '^')));
+    n += usize::from(pattern.get(n) == Some(&']'));
+
+    while n < pattern.len() {
+        match pattern[n] {
+            ']' => return Some(n),
+            '\\' => n += 2,
+            _ => n += 1,
+        }
+    }
+
+    None
+}
+
+/// Returns `true` if `ch` is in the set of a bracket expression, `[a-z]` or
+/// `[!0-9]`, given without its brackets.
+fn bracket_match(set: &[char], ch: char) -> bool {
+    let (negated, mut set) = match set {
+        ['!' | '^', rest @ ..] => (true, rest),
+        _ => (false, set),
+    };
+
+    let mut matched = false;
+    while !set.is_empty() {
+        let (low, rest) = match set {
+            ['\\', low, rest @ ..] | [low, rest @ ..] => (*low, rest),
+            [] => unreachable!(),
+        };
+
+        set = rest;
+        let high = match set {
+            ['-', '\\', high, rest @ ..] | ['-', high, rest @ ..] => {
+                set = rest;
+                *high
+            },
+            _ => low,
+        };
+
+        matched |= (low..=high).contains(&ch);
+    }
+
+    matched != negated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lexer::token::Token;
+    use lexer::{Lexer, Mode};
+
+    fn variables() -> HashMap<String, String> {
+        [
+            ("path", "/usr/local/bin/rush.tar.gz"),
+            ("empty", ""),
+            ("name", "hello World"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_owned(), v.to_owned()))
+        .collect()
+    }
+
+    fn eval(
+        source: &str,
+        variables: &mut HashMap<String, String>,
+    ) -> Result<String, ExpansionError> {
+        let token = Lexer::from_source(source).next().unwrap().unwrap();
+        let Token::Parameter(parameter) = token.node else {
+            panic!("{source} is not a parameter expansion")
+        };
+
+        expand(&parameter, variables)
+    }
+
+    fn assert_expands(cases: &[(&str, &str)]) {
+        for (source, expected) in cases {
+            let expanded = eval(source, &mut variables());
+            assert_eq!(expanded.as_deref(), Ok(*expected), "{source}");
+        }
+    }
+
+    #[test]
+    fn test_unset_and_empty() {
+        assert_expands(&[
+            ("${unset:-default}", "default"),
+            ("${unset-default}", "default"),
+            ("${empty:-default}", "default"),
+            ("${empty-default}", ""),
+            ("${name:-default}", "hello World"),
+            ("${unset:+alt}", ""),
+            ("${empty:+alt}", ""),
+            ("${empty+alt}", "alt"),
+            ("${name:+alt}", "alt"),
+            ("${unset:-$name}", "hello World"),
+            ("${unset:-${empty-x}y}", "y"),
+            ("${unset:-'a b' \"c\\\"\"}", "a b c\""),
+            ("${#path}", "26"),
+            ("${#unset}", "0"),
+        ]);
+    }
+
+    #[test]
+    fn test_assign_and_error() {
+        let mut vars = variables();
+
+        assert_eq!(eval("${empty=x}", &mut vars).as_deref(), Ok(""));
+        assert_eq!(eval("${empty:=x}", &mut vars).as_deref(), Ok("x"));
+        assert_eq!(eval("${unset=y}", &mut vars).as_deref(), Ok("y"));
+        assert_eq!(vars["empty"], "x");
+        assert_eq!(vars["unset"], "y");
+
+        assert_eq!(
+            eval("${name:?oops}", &mut vars).as_deref(),
+            Ok("hello World")
+        );
+        assert_eq!(
+            eval("${missing:?is required}", &mut vars),
+            Err(ExpansionError::Unset {
+                name: "missing".into(),
+                message: "is required".into(),
+            })
+        );
+        assert_eq!(
+            eval("${missing?}", &mut vars).unwrap_err().to_string(),
+            "missing: parameter not set"
+        );
+        assert!(eval("${unset:-$(date)}", &mut vars).is_ok());
+        assert_eq!(
+            eval("${missing:-$(date)}", &mut vars),
+            Err(ExpansionError::Unsupported("$(date)".into()))
+        );
+    }
+
+    #[test]
+    fn test_patterns() {
+        assert_expands(&[
+            ("${path#*/}", "usr/local/bin/rush.tar.gz"),
+            ("${path##*/}", "rush.tar.gz"),
+            ("${path%.*}", "/usr/local/bin/rush.tar"),
+            ("${path%%.*}", "/usr/local/bin/rush"),
+            ("${path#/usr}", "/local/bin/rush.tar.gz"),
+            ("${path#nothing}", "/usr/local/bin/rush.tar.gz"),
+            ("${path/\\//:}", ":usr/local/bin/rush.tar.gz"),
+            ("${path//\\//:}", ":usr:local:bin:rush.tar.gz"),
+            ("${path//[aeiou]/}", "/sr/lcl/bn/rsh.tr.gz"),
+            ("${path//[!a-z]/_}", "_usr_local_bin_rush_tar_gz"),
+            ("${path/l*l/L}", "/usr/L/bin/rush.tar.gz"),
+            ("${name// /-}", "hello-World"),
+            ("${name/'*'/x}", "hello World"),
+            ("${name/o/}", "hell World"),
+            ("${name^}", "Hello World"),
+            ("${name^^}", "HELLO WORLD"),
+            ("${name,,}", "hello world"),
+            ("${unset^^}", ""),
+        ]);
+    }
+
+    #[test]
+    fn test_words_and_strings() {
+        let words = Lexer::from_source("echo ${HOME:-/root}/bin \"${x//a/b}\"")
+            .with_mode(Mode::Command)
+            .map(|t| t.unwrap().node)
+            .collect::<Vec<_>>();
+
+        let Token::Word(word) = &words[1] else {
+            panic!()
+        };
+        assert!(
+            matches!(word.segments(), [Segment::Parameter(_), Segment::Text(t)] if &**t == "/bin")
+        );
+        assert_eq!(words[1].to_source(), "${HOME:-/root}/bin");
+        assert_eq!(words[2].to_source(), "${x//a/b}");
+    }
+}
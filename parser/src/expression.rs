@@ -1,6 +1,14 @@
-use lexer::token::{Identifier, Literal, Operator};
+use lexer::token::{
+    CommandSubstitution, Delimitter, Identifier, Keyword, Literal, MiscOperator, Operator,
+    ParameterExpansion, Token,
+};
+use rush_core::tracker::Span;
 use std::{fmt::Display, write};
 
+use crate::error::ParseError;
+use crate::statement::Block;
+use crate::{Parser, Result};
+
 /// expression -> expression
 ///             | end
 ///
@@ -19,10 +27,28 @@ use std::{fmt::Display, write};
 /// right      -> operand
 ///
 /// operand    -> literal
+///             | command
+///             | parameter
+///             | list
+///             | block
+///             | if
+///             | call
 ///             | expression
 ///
-/// operator   -> [`Operator`]
+/// call       -> operand ( (argument ,)* argument? )
+///
+/// argument   -> (identifier =)? expression
+///
+/// list       -> [ (expression ,)* expression? ]
+///
+/// block      -> { statement* expression? }
+///
+/// if         -> if expression block (else (block | if))?
+///
+/// operator   -> [`Operator`], binding as given by [`Parser::parse_expression`]
 /// literal    -> [`Literal`]
+/// command    -> [`CommandSubstitution`]
+/// parameter  -> [`ParameterExpansion`]
 #[must_use = "An expression tree must be used"]
 pub enum Expression {
     BinaryExp {
@@ -36,9 +62,43 @@ pub enum Expression {
         operator: Operator,
     },
 
+    Call {
+        callee: Box<Expression>,
+        args: Vec<Argument>,
+    },
+
     Expr(Box<Expression>),
     Literal(Literal),
     Identifier(Identifier),
+
+    /// A `$(...)` or `` `...` `` substitution, evaluating to the standard
+    /// output captured from running its source as a command.
+    Command(CommandSubstitution),
+
+    /// A `${name:-word}` style expansion, evaluating to the value of a shell
+    /// parameter transformed as in bash, see [`crate::expansion::expand`].
+    Parameter(ParameterExpansion),
+
+    /// A `[a, b, c]` list.
+    List(Vec<Expression>),
+
+    /// A `{ ... }` block evaluating to its trailing expression.
+    Block(Block),
+
+    /// An `if` evaluating to the value of the block taken, a missing `else`
+    /// evaluates to nothing when the condition does not hold. The `else`
+    /// branch is either a [`Expression::Block`] or another `if`.
+    If {
+        condition: Box<Expression>,
+        then: Block,
+        otherwise: Option<Box<Expression>>,
+    },
+}
+
+/// An argument of an [`Expression::Call`], `name = value` when named.
+pub struct Argument {
+    pub name: Option<Identifier>,
+    pub value: Expression,
 }
 
 impl Display for Expression {
@@ -52,14 +112,228 @@ impl Display for Expression {
                 write!(f, "(unary {} {:?})", operand, operator)
             },
 
+            Expression::Call { callee, args } => {
+                write!(f, "(call {}", callee)?;
+                for arg in args {
+                    match &arg.name {
+                        Some(name) => write!(f, " (named {} {})", name.name(), arg.value)?,
+                        None => write!(f, " {}", arg.value)?,
+                    }
+                }
+                write!(f, ")")
+            },
+
             Expression::Expr(expr) => write!(f, "(expr {})", expr),
             Expression::Literal(literal) => write!(f, "(literal {:?})", literal),
             Expression::Identifier(ident) => write!(f, "(identifier {})", ident),
+            Expression::Command(command) => write!(f, "(command {:?})", command.source()),
+            Expression::Parameter(parameter) => write!(f, "(parameter {})", parameter.to_source()),
+            Expression::List(items) => {
+                write!(f, "(list")?;
+                for item in items {
+                    write!(f, " {}", item)?;
+                }
+                write!(f, ")")
+            },
+            Expression::Block(block) => write!(f, "{}", block),
+            Expression::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                write!(f, "(if {} {}", condition, then)?;
+                if let Some(otherwise) = otherwise {
+                    write!(f, " {}", otherwise)?;
+                }
+                write!(f, ")")
+            },
         }
     }
 }
 
+impl Parser<'_> {
+    /// Parses an expression by precedence climbing.
+    ///
+    /// Binary operators bind as given by [`Operator::binary_precedence`], from
+    /// `*` and `/` binding tightest down to the `..` and `..=` ranges, and are
+    /// all left associative, so `a - b - c` is `(a - b) - c`. The prefix
+    /// operators `!`, `~` and `-` bind tighter than any binary operator and
+    /// parenthesis group an expression into an [`Expression::Expr`].
+    ///
+    /// Parsing stops before the first token that cannot continue the
+    /// expression, like an assignment or a `;`.
+    pub fn parse_expression(&mut self) -> Result<Expression> {
+        self.parse_binary(0)
+    }
+
+    /// Parses operands joined by binary operators binding tighter than
+    /// `min_precedence`.
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<Expression> {
+        let mut left = self.parse_unary()?;
+
+        while let Some(precedence) = self
+            .peek()
+            .and_then(Token::binary_precedence)
+            .filter(|precedence| *precedence > min_precedence)
+        {
+            let Some(Token::Operator(op)) = self.advance()?.map(|token| token.node) else {
+                unreachable!("only operators have a binary precedence")
+            };
+
+            let right = self.parse_binary(precedence)?;
+            left = Expression::BinaryExp {
+                left: Box::new(left),
+                op,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(left)
+    }
+
+    /// Parses an operand along with the prefix operators applied to it and
+    /// the calls following it, calls binding tighter than prefix operators.
+    fn parse_unary(&mut self) -> Result<Expression> {
+        let token = self.expect_token("an expression")?;
+
+        let mut expr = match token.node {
+            Token::Operator(operator) if operator.is_unary() => {
+                let operand = self.parse_unary()?;
+                return Ok(Expression::UnaryOp {
+                    operand: Box::new(operand),
+                    operator,
+                });
+            },
+            Token::Delimitter(Delimitter::LParen) => {
+                let expr = self.parse_expression()?;
+                self.expect(&Token::Delimitter(Delimitter::RParen))?;
+                Expression::Expr(Box::new(expr))
+            },
+            Token::Delimitter(Delimitter::LSquare) => self.parse_list()?,
+            Token::Delimitter(Delimitter::LCurly) => {
+                return self.parse_block().map(Expression::Block)
+            },
+            Token::Keyword(Keyword::If) => return self.parse_if(),
+            _ => match self.parse_primary(token.clone()) {
+                Some(expr) => expr,
+                None => return Err(Self::unexpected("an expression", &token)),
+            },
+        };
+
+        while self.eat(&Token::Delimitter(Delimitter::LParen)) {
+            expr = Expression::Call {
+                callee: Box::new(expr),
+                args: self.parse_arguments()?,
+            };
+        }
+
+        Ok(expr)
+    }
+}
+
+impl Parser<'_> {
+    /// Parses the comma separated items of a list upto its closing `]`, the
+    /// `[` must already be consumed. A trailing comma is allowed.
+    fn parse_list(&mut self) -> Result<Expression> {
+        let close = Token::Delimitter(Delimitter::RSquare);
+        let mut items = vec![];
+
+        while !self.eat(&close) {
+            items.push(self.parse_expression()?);
+
+            if !self.eat(&Token::Delimitter(Delimitter::Comma)) {
+                self.expect(&close)?;
+                break;
+            }
+        }
+
+        Ok(Expression::List(items))
+    }
+
+    /// Parses the comma separated arguments of a call upto the closing `)`,
+    /// the `(` must already be consumed. Named arguments come after the
+    /// positional ones and a trailing comma is allowed.
+    fn parse_arguments(&mut self) -> Result<Vec<Argument>> {
+        let close = Token::Delimitter(Delimitter::RParen);
+        let assign = Token::Operator(Operator::Misc(MiscOperator::Assign));
+        let mut args: Vec<Argument> = vec![];
+
+        while !self.eat(&close) {
+            let named = matches!(self.peek(), Some(Token::Identifier(_)))
+                && self.peek_nth(1) == Some(&assign);
+
+            let name = match named {
+                true => {
+                    let Some(Token::Identifier(name)) = self.advance()?.map(|token| token.node)
+                    else {
+                        unreachable!("the name was peeked")
+                    };
+                    let span = self.previous;
+                    self.advance()?;
+
+                    if args.iter().any(|arg| arg.name.as_ref() == Some(&name)) {
+                        let message = format!("duplicate argument '{}'", name.name());
+                        return Err(ParseError::new(message, span).into());
+                    }
+                    Some(name)
+                },
+                false => None,
+            };
+
+            let start = self.next_start();
+            let value = self.parse_expression()?;
+            if name.is_none() && args.iter().any(|arg| arg.name.is_some()) {
+                let message = "positional argument after a named argument";
+                let span = Span::new(start, self.previous.end());
+                return Err(ParseError::new(message, span).into());
+            }
+
+            args.push(Argument { name, value });
+
+            if !self.eat(&Token::Delimitter(Delimitter::Comma)) {
+                self.expect(&close)?;
+                break;
+            }
+        }
+
+        Ok(args)
+    }
+
+    /// Parses an `if` chain, the `if` must already be consumed.
+    fn parse_if(&mut self) -> Result<Expression> {
+        let condition = self.parse_expression()?;
+        self.expect(&Token::Delimitter(Delimitter::LCurly))?;
+        let then = self.parse_block()?;
+
+        let otherwise = match self.eat(&Token::Keyword(Keyword::Else)) {
+            true => {
+                let expected = "'{' or 'if'";
+                let token = self.expect_token(expected)?;
+                let branch = match token.node {
+                    Token::Delimitter(Delimitter::LCurly) => Expression::Block(self.parse_block()?),
+                    Token::Keyword(Keyword::If) => self.parse_if()?,
+                    _ => return Err(Self::unexpected(expected, &token)),
+                };
+                Some(Box::new(branch))
+            },
+            false => None,
+        };
+
+        Ok(Expression::If {
+            condition: Box::new(condition),
+            then,
+            otherwise,
+        })
+    }
+}
+
 impl Expression {
+    /// Returns `true` if the expression ends with a block, so that it can
+    /// stand as a statement without a `;`.
+    pub fn ends_with_block(&self) -> bool {
+        matches!(self, Self::Block(_) | Self::If { .. })
+    }
+
     pub fn yield_expr(&self) -> String {
         if let Self::Identifier(ident) = self {
             ident.name().to_owned()
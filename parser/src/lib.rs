@@ -1,18 +1,30 @@
 #![allow(dead_code)]
 
 pub mod error;
+pub mod expansion;
 pub mod expression;
 pub mod statement;
 
-use lexer::{token::Token, Lexer};
+use error::ParseError;
+use expression::Expression;
+use lexer::token::{Delimitter, Identifier, Token};
+use lexer::Lexer;
 use rush_core::lazybuf::LazyBuf;
+use rush_core::tracker::{Span, Spanned, Tracker};
 use statement::Statement;
+use std::collections::HashMap;
 
 type Result<T> = std::result::Result<T, error::Error>;
 
 pub struct Parser<'parser> {
     generator: LazyBuf<'parser, Lexer<'parser>>,
-    stack: Vec<Token>,
+    stack: Vec<Spanned<Token>>,
+    previous: Span,
+    boundary: Option<Delimitter>,
+    open_blocks: usize,
+    scopes: Vec<HashMap<Identifier, bool>>,
+    loops: Vec<Option<Identifier>>,
+    functions: usize,
 }
 
 impl<'p> Parser<'p> {
@@ -20,11 +32,160 @@ impl<'p> Parser<'p> {
         let generator = rush_core::BufferExt::lazy_buf(generator);
         let stack = vec![];
 
-        Self { generator, stack }
+        Self {
+            generator,
+            stack,
+            previous: Span::default(),
+            boundary: None,
+            open_blocks: 0,
+            scopes: vec![HashMap::new()],
+            loops: vec![],
+            functions: 0,
+        }
+    }
+
+    /// Converts a single token to the expression it stands for, returns `None`
+    /// if the token cannot start an expression on its own.
+    fn parse_primary(&mut self, token: Spanned<Token>) -> Option<Expression> {
+        match token.node {
+            Token::Literal(literal) => Some(Expression::Literal(literal)),
+            Token::Identifier(ident) => Some(Expression::Identifier(ident)),
+            Token::Substitution(command) => Some(Expression::Command(command)),
+            Token::Parameter(parameter) => Some(Expression::Parameter(parameter)),
+            _ => None,
+        }
+    }
+
+    /// Returns the next token without consuming it, a lexer error is left for
+    /// [`Parser::advance`] to report.
+    fn peek(&self) -> Option<&Token> {
+        self.peek_nth(0)
+    }
+
+    /// Returns the `n`th token after the next one without consuming anything.
+    fn peek_nth(&self, n: usize) -> Option<&Token> {
+        match self.generator.peek_nth(n)? {
+            Ok(token) => Some(&token.node),
+            Err(_) => None,
+        }
+    }
+
+    /// Returns where the next token starts, or where the previous one ends at
+    /// the end of the input.
+    fn next_start(&self) -> Tracker {
+        match self.generator.peek_nth(0) {
+            Some(Ok(token)) => token.span.start(),
+            _ => self.previous.end(),
+        }
+    }
+
+    /// Consumes the next token, returns `None` at the end of the input.
+    fn advance(&mut self) -> Result<Option<Spanned<Token>>> {
+        match self.generator.next() {
+            Some(Ok(token)) => {
+                self.previous = token.span;
+                self.boundary = match &token.node {
+                    Token::Delimitter(d @ (Delimitter::SemiColon | Delimitter::RCurly)) => {
+                        Some(d.clone())
+                    },
+                    _ => None,
+                };
+                Ok(Some(token.clone()))
+            },
+            Some(Err(e)) => Err(e.clone().into()),
+            None => Ok(None),
+        }
+    }
+
+    /// Consumes the next token if it is `expected`.
+    fn eat(&mut self, expected: &Token) -> bool {
+        let matched = self.peek() == Some(expected);
+        if matched {
+            // Only tokens lexed without errors can be peeked.
+            let _ = self.advance();
+        }
+        matched
+    }
+
+    /// Consumes the next token, failing with an error naming `expected` if
+    /// there is none.
+    fn expect_token(&mut self, expected: &str) -> Result<Spanned<Token>> {
+        match self.advance()? {
+            Some(token) => Ok(token),
+            None => Err(self.error_at_end(expected)),
+        }
+    }
+
+    /// Consumes the next token if it is `expected`, fails with an error
+    /// otherwise.
+    fn expect(&mut self, expected: &Token) -> Result<Spanned<Token>> {
+        let what = format!("'{expected}'");
+        let token = self.expect_token(&what)?;
+
+        match &token.node == expected {
+            true => Ok(token),
+            false => Err(Self::unexpected(&what, &token)),
+        }
+    }
+
+    /// Skips the rest of a statement that failed with `error`, upto and
+    /// including the next `;` or `}` outside of every block left open by the
+    /// error, and leaves every scope, loop and function it was in.
+    ///
+    /// The token an error was found at is already consumed, so when it is a
+    /// `;` or `}` it counts towards the skipped ones.
+    fn recover(&mut self, error: &error::Error) {
+        let found = match error {
+            error::Error::Parser(e) if e.span() == self.previous => self.boundary.take(),
+            _ => None,
+        };
+
+        let mut depth = std::mem::take(&mut self.open_blocks);
+        let mut done = match found {
+            Some(Delimitter::RCurly) if depth > 0 => {
+                depth -= 1;
+                depth == 0
+            },
+            Some(_) => depth == 0,
+            None => false,
+        };
+
+        while !done {
+            let token = match self.advance() {
+                Ok(Some(token)) => token.node,
+                Ok(None) => break,
+                Err(_) => continue,
+            };
+
+            done = match token {
+                Token::Delimitter(Delimitter::LCurly) => {
+                    depth += 1;
+                    false
+                },
+                Token::Delimitter(Delimitter::RCurly) if depth > 0 => {
+                    depth -= 1;
+                    depth == 0
+                },
+                Token::Delimitter(Delimitter::SemiColon | Delimitter::RCurly) => depth == 0,
+                _ => false,
+            };
+        }
+
+        self.scopes.truncate(1);
+        self.loops.clear();
+        self.functions = 0;
+    }
+
+    /// An error for finding `token` where `expected` should have been.
+    fn unexpected(expected: &str, token: &Spanned<Token>) -> error::Error {
+        let message = format!("expected {expected}, found '{}'", token.node);
+        ParseError::new(message, token.span).into()
     }
 
-    fn parse_let(&mut self) -> Option<<Self as Iterator>::Item> {
-        None
+    /// An error for the input ending where `expected` should have been.
+    fn error_at_end(&self, expected: &str) -> error::Error {
+        let end = Span::new(self.previous.end(), self.previous.end());
+        ParseError::new(format!("expected {expected}, found end of input"), end).into()
     }
 }
 
@@ -32,14 +193,562 @@ impl Iterator for Parser<'_> {
     type Item = Result<Statement>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let token = match self.generator.next()? {
-            Ok(token) => token,
-            Err(e) => return Some(Err(e.clone().into())),
+        match self.peek() {
+            None => match self.advance() {
+                Ok(_) => None,
+                Err(e) => Some(Err(e)),
+            },
+            Some(_) => {
+                let statement = self.parse_statement().and_then(|statement| {
+                    let terminated = self.eat(&Token::Delimitter(Delimitter::SemiColon));
+                    if statement.needs_terminator() && !terminated && self.peek().is_some() {
+                        self.expect(&Token::Delimitter(Delimitter::SemiColon))?;
+                    }
+                    Ok(statement)
+                });
+
+                if let Err(e) = &statement {
+                    self.recover(e);
+                }
+                Some(statement)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_substitution_expression() {
+        let lexer = Lexer::new(Box::new("$(ls $(pwd)) `date`".chars()));
+        let mut parser = Parser::new(Lexer::new(Box::new("".chars())));
+
+        let expressions = lexer
+            .map(|token| parser.parse_primary(token.unwrap()).unwrap().to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            expressions,
+            [r#"(command "ls $(pwd)")"#, r#"(command "date")"#]
+        );
+    }
+
+    fn parse(source: &str) -> Result<String> {
+        let mut parser = Parser::new(Lexer::new(Box::new(source.chars())));
+        parser.parse_expression().map(|expr| expr.to_string())
+    }
+
+    fn literal(n: isize) -> String {
+        format!("(literal Number({n}))")
+    }
+
+    fn ident(name: &str) -> String {
+        format!("(identifier Identifier({name}))")
+    }
+
+    #[test]
+    fn test_binary_precedence() {
+        let (one, two, three) = (literal(1), literal(2), literal(3));
+
+        assert_eq!(
+            parse("1 + 2 * 3").unwrap(),
+            format!("(binary {one} Arithmetic(Plus) (binary {two} Arithmetic(Multiply) {three}))")
+        );
+        assert_eq!(
+            parse("1 - 2 - 3").unwrap(),
+            format!("(binary (binary {one} Arithmetic(Minus) {two}) Arithmetic(Minus) {three})")
+        );
+        assert_eq!(
+            parse("(1 + 2) * 3").unwrap(),
+            format!(
+                "(binary (expr (binary {one} Arithmetic(Plus) {two})) Arithmetic(Multiply) {three})"
+            )
+        );
+        assert_eq!(
+            parse("1 << 2 & 3").unwrap(),
+            format!("(binary (binary {one} Bitwise(LeftShift) {two}) Bitwise(BitWiseAnd) {three})")
+        );
+        assert_eq!(
+            parse("1 | 2 ^ 3").unwrap(),
+            format!("(binary {one} Bitwise(BitWiseOr) (binary {two} Bitwise(Xor) {three}))")
+        );
+        assert_eq!(
+            parse("a < b == c").unwrap(),
+            format!(
+                "(binary (binary {} Relational(LessThan) {}) Relational(Equal) {})",
+                ident("a"),
+                ident("b"),
+                ident("c")
+            )
+        );
+        assert_eq!(
+            parse("a || b && c").unwrap(),
+            format!(
+                "(binary {} Conditional(Or) (binary {} Conditional(And) {}))",
+                ident("a"),
+                ident("b"),
+                ident("c")
+            )
+        );
+        assert_eq!(
+            parse("0 .. n + 1").unwrap(),
+            format!(
+                "(binary {} Range(ExclusiveRange) (binary {} Arithmetic(Plus) {one}))",
+                literal(0),
+                ident("n")
+            )
+        );
+    }
+
+    #[test]
+    fn test_prefix_operators() {
+        assert_eq!(
+            parse("-a * b").unwrap(),
+            format!(
+                "(binary (unary {} Arithmetic(Minus)) Arithmetic(Multiply) {})",
+                ident("a"),
+                ident("b")
+            )
+        );
+        assert_eq!(
+            parse("!~x").unwrap(),
+            format!(
+                "(unary (unary {} Bitwise(BitWiseNot)) Conditional(Not))",
+                ident("x")
+            )
+        );
+        assert_eq!(
+            parse("1 - -2").unwrap(),
+            format!(
+                "(binary {} Arithmetic(Minus) (unary {} Arithmetic(Minus)))",
+                literal(1),
+                literal(2)
+            )
+        );
+    }
+
+    #[test]
+    fn test_expression_errors() {
+        let message = |source: &str| match parse(source) {
+            Err(error::Error::Parser(e)) => e.message().to_owned(),
+            other => panic!("{source}: {:?}", other.map_err(|e| e.to_string())),
         };
 
-        match token {
-            Token::Keyword(lexer::token::Keyword::Let) => self.parse_let(),
-            _ => None,
+        assert_eq!(message("1 +"), "expected an expression, found end of input");
+        assert_eq!(message("(1 + 2"), "expected ')', found end of input");
+        assert_eq!(message("* 2"), "expected an expression, found '*'");
+        assert_eq!(message("(1]"), "expected ')', found ']'");
+    }
+
+    #[test]
+    fn test_expression_statements() {
+        let source = "1 + 2; x";
+        let statements = Parser::new(Lexer::new(Box::new(source.chars())))
+            .map(|statement| match statement.unwrap() {
+                Statement::Expression(expr) => expr.to_string(),
+                _ => panic!(),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[1], ident("x"));
+    }
+
+    fn statements(source: &str) -> Vec<Result<Statement>> {
+        Parser::new(Lexer::new(Box::new(source.chars()))).collect()
+    }
+
+    fn parse_error(source: &str) -> String {
+        match statements(source).into_iter().find_map(Result::err) {
+            Some(error::Error::Parser(e)) => e.message().to_owned(),
+            Some(e) => panic!("{source}: {e}"),
+            None => panic!("{source}: parsed without errors"),
         }
     }
+
+    #[test]
+    fn test_declarations() {
+        let parsed = statements("let x: int = 1 + 2; const NAME = \"rush\"; x += NAME;")
+            .into_iter()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        let [Statement::Let { ident, ty, expr }, Statement::Const {
+            ident: name,
+            ty: None,
+            ..
+        }, Statement::Assign {
+            ident: target, op, ..
+        }] = &parsed[..]
+        else {
+            panic!("unexpected statements");
+        };
+
+        assert_eq!(ident.name(), "x");
+        assert_eq!(ty.as_ref().map(Identifier::name), Some("int"));
+        assert_eq!(
+            expr.to_string(),
+            "(binary (literal Number(1)) Arithmetic(Plus) (literal Number(2)))"
+        );
+        assert_eq!(name.name(), "NAME");
+        assert_eq!(target.name(), "x");
+        assert!(op.is_assignment());
+    }
+
+    #[test]
+    fn test_constants_cannot_be_reassigned() {
+        assert_eq!(
+            parse_error("const X = 1; X = 2;"),
+            "cannot assign to constant 'X'"
+        );
+        assert_eq!(
+            parse_error("const X = 1; X -= 2;"),
+            "cannot assign to constant 'X'"
+        );
+        assert!(statements("const X = 1; let X = 2; X = 3;")
+            .iter()
+            .all(Result::is_ok));
+        assert_eq!(
+            parse_error("1 + 2 = 3;"),
+            "invalid left-hand side of assignment"
+        );
+
+        let target = |source: &str| match statements(source).into_iter().find_map(Result::err) {
+            Some(error::Error::Parser(e)) => e.span().source_text(source).map(str::to_owned),
+            _ => None,
+        };
+        assert_eq!(target("x; a + b = 1;").as_deref(), Some("a + b"));
+        assert_eq!(target("const X = 1; (X) = 2;").as_deref(), Some("(X)"));
+        assert_eq!(target("const X = 1;  X += 2;").as_deref(), Some("X"));
+        assert_eq!(target("f() = 1;").as_deref(), Some("f()"));
+        assert_eq!(target("f(1,) = 2;").as_deref(), Some("f(1,)"));
+        assert_eq!(target("f(a = 1, g());").as_deref(), Some("g()"));
+
+        let Some(Err(error::Error::Parser(e))) = statements("let x = f()").pop() else {
+            panic!("'let x = f()' parsed without errors");
+        };
+        assert_eq!(e.span().start().get_col(), 11);
+    }
+
+    #[test]
+    fn test_declaration_errors() {
+        assert_eq!(parse_error("let = 1;"), "expected an identifier, found '='");
+        assert_eq!(
+            parse_error("const 1 = 1;"),
+            "expected an identifier, found '1'"
+        );
+        assert_eq!(parse_error("let x 1;"), "expected '=', found '1'");
+        assert_eq!(parse_error("let x: = 1;"), "expected a type, found '='");
+        assert_eq!(parse_error("let x = 1"), "expected ';', found end of input");
+        assert_eq!(parse_error("let x = 1 let"), "expected ';', found 'let'");
+        assert_eq!(
+            parse_error("let x ="),
+            "expected an expression, found end of input"
+        );
+    }
+
+    #[test]
+    fn test_statement_terminators() {
+        assert_eq!(parse_error("a b"), "expected ';', found 'b'");
+        assert_eq!(parse_error("x = 1 y = 2"), "expected ';', found 'y'");
+        assert!(statements("a; b = 1;\nc")
+            .iter()
+            .chain(&statements("if a {} { b } while c {} d"))
+            .all(Result::is_ok));
+    }
+
+    #[test]
+    fn test_error_recovery() {
+        let outcomes = |source: &str| {
+            statements(source)
+                .into_iter()
+                .map(|statement| match statement {
+                    Ok(statement) => statement.to_string(),
+                    Err(error::Error::Parser(e)) => e.message().to_owned(),
+                    Err(e) => e.to_string(),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            outcomes("f() = 1; x"),
+            [
+                "invalid left-hand side of assignment".to_owned(),
+                ident("x")
+            ]
+        );
+        assert_eq!(
+            outcomes("{ let a = 1; a b; { c } } d"),
+            ["expected ';', found 'b'".to_owned(), ident("d")]
+        );
+        assert_eq!(
+            outcomes("x = ; y"),
+            ["expected an expression, found ';'".to_owned(), ident("y")]
+        );
+        assert_eq!(
+            outcomes("while a { fn f() { return 1 2; } } break;"),
+            ["expected ';', found '2'", "'break' outside of a loop"]
+        );
+        assert_eq!(
+            outcomes("if a { b = } c; } d"),
+            [
+                "expected an expression, found '}'".to_owned(),
+                ident("c"),
+                "expected an expression, found '}'".to_owned(),
+                ident("d")
+            ]
+        );
+    }
+
+    fn render(source: &str) -> Vec<String> {
+        statements(source)
+            .into_iter()
+            .map(|statement| statement.unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_if_expressions() {
+        let block = |n: isize| format!("(block (value {}))", literal(n));
+        let (one, two, three) = (block(1), block(2), block(3));
+
+        assert_eq!(
+            render("let x = if a { 1 } else if b { 2 } else { 3 };"),
+            [format!(
+                "(let x (if {} {one} (if {} {two} {three})))",
+                ident("a"),
+                ident("b")
+            )]
+        );
+        assert_eq!(
+            render("if a { b = 1; } c"),
+            [
+                format!(
+                    "(if {} (block (assign b Misc(Assign) {})))",
+                    ident("a"),
+                    literal(1)
+                ),
+                ident("c")
+            ]
+        );
+        assert_eq!(
+            render("1 + if a { 2 } else { 3 }"),
+            [format!(
+                "(binary {} Arithmetic(Plus) (if {} {two} {three}))",
+                literal(1),
+                ident("a")
+            )]
+        );
+    }
+
+    #[test]
+    fn test_blocks() {
+        let (one, a) = (literal(1), ident("a"));
+        let sum = format!("(binary {a} Arithmetic(Plus) {one})");
+        assert_eq!(
+            render("{ let a = 1; if a { a } a + 1 }"),
+            [format!(
+                "(block (let a {one}) (if {a} (block (value {a}))) (value {sum}))"
+            )]
+        );
+        assert_eq!(render("{ a; }"), [format!("(block {})", ident("a"))]);
+        assert_eq!(render("{}"), ["(block)"]);
+        assert_eq!(
+            render("let x = { { 1 } };"),
+            [format!("(let x (block (value (block (value {one})))))")]
+        );
+        assert_eq!(
+            render("if a { { 1 } } else { 2 }"),
+            [format!(
+                "(if {a} (block (value (block (value {one})))) (block (value {})))",
+                literal(2)
+            )]
+        );
+        assert_eq!(
+            render("{ { 1 }; }"),
+            [format!("(block (block (value {one})))")]
+        );
+    }
+
+    #[test]
+    fn test_block_scopes() {
+        assert!(
+            statements("const X = 1; { let X = 2; X = 3; } { const Y = 1; } Y = 2;")
+                .iter()
+                .all(Result::is_ok)
+        );
+        assert_eq!(
+            parse_error("const X = 1; { let Y = 2; X = 3; }"),
+            "cannot assign to constant 'X'"
+        );
+        assert_eq!(
+            parse_error("let X = 1; { const X = 2; } { X = 3; X += 1 } { const X = 0; X = 1 }"),
+            "cannot assign to constant 'X'"
+        );
+    }
+
+    #[test]
+    fn test_block_errors() {
+        assert_eq!(parse_error("{ a b }"), "expected ';', found 'b'");
+        assert_eq!(parse_error("{ a"), "expected '}', found end of input");
+        assert_eq!(parse_error("if a 1"), "expected '{', found '1'");
+        assert_eq!(
+            parse_error("if a {} else 1"),
+            "expected '{' or 'if', found '1'"
+        );
+    }
+
+    #[test]
+    fn test_loops() {
+        let (zero, one, a) = (literal(0), literal(1), ident("a"));
+
+        assert_eq!(
+            render("while a { a -= 1; if a { continue } }"),
+            [format!(
+                "(while {a} (block (assign a Arithmetic(DecrAssign) {one}) \
+                 (value (if {a} (block (continue))))))"
+            )]
+        );
+        assert_eq!(
+            render("for i in 0..=a { break; }"),
+            [format!(
+                "(for i (binary {zero} Range(InclusiveRange) {a}) (block (break)))"
+            )]
+        );
+        assert_eq!(
+            render("for x in [1, a,] { x }"),
+            [format!(
+                "(for x (list {one} {a}) (block (value {})))",
+                ident("x")
+            )]
+        );
+        assert_eq!(
+            render("outer: for x in [] { inner: while x { break outer; continue inner } }"),
+            [format!(
+                "(outer: for x (list) (block (inner: while {} (block (break outer) \
+                 (continue inner)))))",
+                ident("x")
+            )]
+        );
+    }
+
+    #[test]
+    fn test_loop_errors() {
+        assert_eq!(parse_error("break;"), "'break' outside of a loop");
+        assert_eq!(
+            parse_error("if a { continue }"),
+            "'continue' outside of a loop"
+        );
+        assert_eq!(parse_error("while a {} break"), "'break' outside of a loop");
+        assert_eq!(
+            parse_error("outer: while a { break inner; }"),
+            "undeclared label 'inner'"
+        );
+        assert_eq!(
+            parse_error("a: while x {} while y { break a; }"),
+            "undeclared label 'a'"
+        );
+        assert_eq!(
+            parse_error("for 1 in a {}"),
+            "expected an identifier, found '1'"
+        );
+        assert_eq!(parse_error("for x a {}"), "expected 'in', found 'a'");
+        assert_eq!(parse_error("while a b"), "expected '{', found 'b'");
+        assert_eq!(
+            parse_error("a: if b {}"),
+            "expected 'while' or 'for', found 'if'"
+        );
+        assert_eq!(parse_error("[1 2]"), "expected ']', found '2'");
+    }
+
+    #[test]
+    fn test_functions() {
+        let (one, two, a, b) = (literal(1), literal(2), ident("a"), ident("b"));
+
+        assert_eq!(
+            render("fn add(a, b: int = 1) -> int { return a + b; }"),
+            [format!(
+                "(fn add ((param a) (param b: int {one})) -> int \
+                 (block (return (binary {a} Arithmetic(Plus) {b}))))"
+            )]
+        );
+        assert_eq!(
+            render("fn f() { if a { return } 2 }"),
+            [format!(
+                "(fn f () (block (if {a} (block (return))) (value {two})))"
+            )]
+        );
+        assert_eq!(
+            render("fn f(a,) { a = 1; a }"),
+            [format!(
+                "(fn f ((param a)) (block (assign a Misc(Assign) {one}) (value {a})))"
+            )]
+        );
+        assert_eq!(
+            render("while a { fn f() { return 1; } }"),
+            [format!(
+                "(while {a} (block (fn f () (block (return {one})))))"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_calls() {
+        let (one, two, a) = (literal(1), literal(2), ident("a"));
+
+        assert_eq!(
+            render("f(1, a, b = 2,)"),
+            [format!("(call {} {one} {a} (named b {two}))", ident("f"))]
+        );
+        assert_eq!(
+            render("-f()(a) * 2"),
+            [format!(
+                "(binary (unary (call (call {}) {a}) Arithmetic(Minus)) \
+                 Arithmetic(Multiply) {two})",
+                ident("f")
+            )]
+        );
+        assert_eq!(
+            render("f(a == 1)"),
+            [format!(
+                "(call {} (binary {a} Relational(Equal) {one}))",
+                ident("f")
+            )]
+        );
+    }
+
+    #[test]
+    fn test_function_errors() {
+        assert_eq!(parse_error("return 1;"), "'return' outside of a function");
+        assert_eq!(
+            parse_error("fn f() {} return"),
+            "'return' outside of a function"
+        );
+        assert_eq!(
+            parse_error("while a { fn f() { break; } }"),
+            "'break' outside of a loop"
+        );
+        assert_eq!(parse_error("fn f(a, a) {}"), "duplicate parameter 'a'");
+        assert_eq!(
+            parse_error("fn f(a = 1, b) {}"),
+            "parameter 'b' without a default follows one with a default"
+        );
+        assert_eq!(parse_error("fn f(a) -> 1 {}"), "expected a type, found '1'");
+        assert_eq!(
+            parse_error("fn (a) {}"),
+            "expected an identifier, found '('"
+        );
+        assert_eq!(
+            parse_error("f(a = 1, 2)"),
+            "positional argument after a named argument"
+        );
+        assert_eq!(parse_error("f(a = 1, a = 2)"), "duplicate argument 'a'");
+        assert_eq!(parse_error("f(1 2)"), "expected ')', found '2'");
+        assert_eq!(
+            parse_error("fn f() {} f = 1;"),
+            "cannot assign to constant 'f'"
+        );
+    }
 }
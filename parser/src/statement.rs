@@ -1,14 +1,592 @@
-use lexer::token::Identifier;
+use lexer::token::{Delimitter, Identifier, Keyword, MiscOperator, Operator, Token};
+use rush_core::tracker::Span;
+use std::collections::HashMap;
+use std::{fmt::Display, write};
 
+use crate::error::ParseError;
 use crate::expression::Expression;
+use crate::{Parser, Result};
 
 #[derive(Default)]
 pub enum Statement {
+    /// `let name: type = expr;`, a variable that can be assigned to later.
     Let {
         ident: Identifier,
+        ty: Option<Identifier>,
         expr: Expression,
     },
 
+    /// `const NAME: type = expr;`, a binding that cannot be assigned to.
+    Const {
+        ident: Identifier,
+        ty: Option<Identifier>,
+        expr: Expression,
+    },
+
+    /// `name = expr` or a compound assignment like `name += expr`.
+    Assign {
+        ident: Identifier,
+        op: Operator,
+        expr: Expression,
+    },
+
+    /// An expression evaluated for its effects, followed by `;` unless it
+    /// ends with a block, like an `if`.
+    Expression(Expression),
+
+    /// A `{ ... }` block standing on its own.
+    Block(Block),
+
+    /// `label: while condition { ... }`, the label is optional.
+    While {
+        label: Option<Identifier>,
+        condition: Expression,
+        body: Block,
+    },
+
+    /// `label: for binding in iterable { ... }`, iterating over a `..` or
+    /// `..=` range or a list. The binding is only in scope in the body.
+    For {
+        label: Option<Identifier>,
+        binding: Identifier,
+        iterable: Expression,
+        body: Block,
+    },
+
+    /// `break label`, leaving the loop with the label or the innermost one.
+    Break(Option<Identifier>),
+
+    /// `continue label`, skipping to the next iteration of the loop with the
+    /// label or the innermost one.
+    Continue(Option<Identifier>),
+
+    /// `fn name(a, b: type = default) -> type { ... }`.
+    Function(Function),
+
+    /// `return expr`, the value is optional.
+    Return(Option<Expression>),
+
     #[default]
     End,
 }
+
+/// The statements between `{` and `}`, with a scope of their own. A trailing
+/// expression without a `;` is the value of the block.
+pub struct Block {
+    pub statements: Vec<Statement>,
+    pub value: Option<Box<Expression>>,
+}
+
+/// A function declaration, its name is a constant in the enclosing scope.
+pub struct Function {
+    pub name: Identifier,
+    pub params: Vec<Parameter>,
+    pub ret: Option<Identifier>,
+    pub body: Block,
+}
+
+/// A parameter of a [`Function`], the parameters with a default value come
+/// after the ones without.
+pub struct Parameter {
+    pub name: Identifier,
+    pub ty: Option<Identifier>,
+    pub default: Option<Expression>,
+}
+
+impl Display for Statement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let typed = |ident: &Identifier, ty: &Option<Identifier>| match ty {
+            Some(ty) => format!("{}: {}", ident.name(), ty.name()),
+            None => ident.name().to_owned(),
+        };
+        let labeled = |label: &Option<Identifier>, name: &str| match label {
+            Some(label) => format!("{}: {}", label.name(), name),
+            None => name.to_owned(),
+        };
+        let jump = |label: &Option<Identifier>, name: &str| match label {
+            Some(label) => format!("({} {})", name, label.name()),
+            None => format!("({})", name),
+        };
+
+        match self {
+            Statement::Let { ident, ty, expr } => write!(f, "(let {} {})", typed(ident, ty), expr),
+            Statement::Const { ident, ty, expr } => {
+                write!(f, "(const {} {})", typed(ident, ty), expr)
+            },
+            Statement::Assign { ident, op, expr } => {
+                write!(f, "(assign {} {:?} {})", ident.name(), op, expr)
+            },
+            Statement::Expression(expr) => write!(f, "{}", expr),
+            Statement::Block(block) => write!(f, "{}", block),
+            Statement::While {
+                label,
+                condition,
+                body,
+            } => write!(f, "({} {} {})", labeled(label, "while"), condition, body),
+            Statement::For {
+                label,
+                binding,
+                iterable,
+                body,
+            } => write!(
+                f,
+                "({} {} {} {})",
+                labeled(label, "for"),
+                binding.name(),
+                iterable,
+                body
+            ),
+            Statement::Break(label) => write!(f, "{}", jump(label, "break")),
+            Statement::Continue(label) => write!(f, "{}", jump(label, "continue")),
+            Statement::Function(function) => write!(f, "{}", function),
+            Statement::Return(Some(expr)) => write!(f, "(return {})", expr),
+            Statement::Return(None) => write!(f, "(return)"),
+            Statement::End => write!(f, "(end)"),
+        }
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(fn {} (", self.name.name())?;
+        for (n, param) in self.params.iter().enumerate() {
+            if n > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", param)?;
+        }
+        write!(f, ")")?;
+        if let Some(ret) = &self.ret {
+            write!(f, " -> {}", ret.name())?;
+        }
+        write!(f, " {})", self.body)
+    }
+}
+
+impl Display for Parameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(param {}", self.name.name())?;
+        if let Some(ty) = &self.ty {
+            write!(f, ": {}", ty.name())?;
+        }
+        if let Some(default) = &self.default {
+            write!(f, " {}", default)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl Display for Block {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(block")?;
+        for statement in &self.statements {
+            write!(f, " {}", statement)?;
+        }
+        if let Some(value) = &self.value {
+            write!(f, " (value {})", value)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl Statement {
+    /// Returns `true` if the statement must be followed by a `;` when another
+    /// statement comes after it. Declarations consume their own `;` and the
+    /// statements ending with a block need none.
+    pub fn needs_terminator(&self) -> bool {
+        match self {
+            Statement::Expression(expr) => !expr.ends_with_block(),
+            Statement::Assign { .. }
+            | Statement::Break(_)
+            | Statement::Continue(_)
+            | Statement::Return(_) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Parser<'_> {
+    /// Parses the statement starting at the next token, a trailing `;` is left
+    /// for the caller.
+    pub(crate) fn parse_statement(&mut self) -> Result<Statement> {
+        match self.peek() {
+            Some(Token::Keyword(keyword @ (Keyword::Let | Keyword::Const))) => {
+                let keyword = keyword.clone();
+                self.advance()?;
+                self.parse_let(keyword)
+            },
+            Some(Token::Delimitter(Delimitter::LCurly)) => {
+                self.advance()?;
+                self.parse_block().map(Statement::Block)
+            },
+            Some(Token::Identifier(_))
+                if self.peek_nth(1) == Some(&Token::Delimitter(Delimitter::Colon)) =>
+            {
+                let Some(Token::Identifier(label)) = self.advance()?.map(|token| token.node) else {
+                    unreachable!("the label was peeked")
+                };
+                self.advance()?;
+
+                let expected = "'while' or 'for'";
+                let token = self.expect_token(expected)?;
+                match token.node {
+                    Token::Keyword(Keyword::While) => self.parse_while(Some(label)),
+                    Token::Keyword(Keyword::For) => self.parse_for(Some(label)),
+                    _ => Err(Self::unexpected(expected, &token)),
+                }
+            },
+            Some(Token::Keyword(Keyword::While)) => {
+                self.advance()?;
+                self.parse_while(None)
+            },
+            Some(Token::Keyword(Keyword::For)) => {
+                self.advance()?;
+                self.parse_for(None)
+            },
+            Some(Token::Keyword(Keyword::Break | Keyword::Continue)) => self.parse_jump(),
+            Some(Token::Keyword(Keyword::Fn)) => {
+                self.advance()?;
+                self.parse_function().map(Statement::Function)
+            },
+            Some(Token::Keyword(Keyword::Return)) => self.parse_return(),
+            _ => self.parse_expression_statement(),
+        }
+    }
+
+    /// Parses a `while` loop, the `while` must already be consumed.
+    fn parse_while(&mut self, label: Option<Identifier>) -> Result<Statement> {
+        let condition = self.parse_expression()?;
+        let body = self.parse_loop_body(label.clone(), None)?;
+
+        Ok(Statement::While {
+            label,
+            condition,
+            body,
+        })
+    }
+
+    /// Parses a `for` loop, the `for` must already be consumed.
+    fn parse_for(&mut self, label: Option<Identifier>) -> Result<Statement> {
+        let binding = self.expect_identifier()?;
+
+        self.expect(&Token::Keyword(Keyword::In))?;
+        let iterable = self.parse_expression()?;
+        let body = self.parse_loop_body(label.clone(), Some(binding.clone()))?;
+
+        Ok(Statement::For {
+            label,
+            binding,
+            iterable,
+            body,
+        })
+    }
+
+    /// Parses the block of a loop, within which `break` and `continue` can be
+    /// used and `binding` is declared.
+    fn parse_loop_body(
+        &mut self,
+        label: Option<Identifier>,
+        binding: Option<Identifier>,
+    ) -> Result<Block> {
+        self.expect(&Token::Delimitter(Delimitter::LCurly))?;
+
+        self.loops.push(label);
+        self.scopes.push(
+            binding
+                .into_iter()
+                .map(|binding| (binding, false))
+                .collect(),
+        );
+        let body = self.parse_block();
+        self.scopes.pop();
+        self.loops.pop();
+
+        body
+    }
+
+    /// Parses a `break` or `continue` with its optional label, both are only
+    /// valid inside a loop and the label has to be one of an enclosing loop.
+    fn parse_jump(&mut self) -> Result<Statement> {
+        let token = self.expect_token("'break' or 'continue'")?;
+
+        let label = match self.peek() {
+            Some(Token::Identifier(_)) => match self.advance()?.map(|token| token.node) {
+                Some(Token::Identifier(label)) => Some(label),
+                _ => unreachable!("the label was peeked"),
+            },
+            _ => None,
+        };
+
+        if self.loops.is_empty() {
+            let message = format!("'{}' outside of a loop", token.node);
+            return Err(ParseError::new(message, token.span).into());
+        }
+
+        if let Some(label) = label.as_ref().filter(|label| {
+            !self
+                .loops
+                .iter()
+                .flatten()
+                .any(|enclosing| enclosing == *label)
+        }) {
+            let message = format!("undeclared label '{}'", label.name());
+            return Err(ParseError::new(message, self.previous).into());
+        }
+
+        match token.node {
+            Token::Keyword(Keyword::Break) => Ok(Statement::Break(label)),
+            _ => Ok(Statement::Continue(label)),
+        }
+    }
+
+    /// Parses the statements of a block upto its closing `}`, the `{` must
+    /// already be consumed. Declarations inside the block go out of scope at
+    /// its end, a block left open by an error is closed by the recovery from
+    /// it.
+    pub(crate) fn parse_block(&mut self) -> Result<Block> {
+        self.scopes.push(HashMap::new());
+        self.open_blocks += 1;
+        let block = self.parse_block_statements();
+        if block.is_ok() {
+            self.open_blocks -= 1;
+        }
+        self.scopes.pop();
+        block
+    }
+
+    fn parse_block_statements(&mut self) -> Result<Block> {
+        let close = Token::Delimitter(Delimitter::RCurly);
+        let mut statements = vec![];
+        let mut value = None;
+
+        loop {
+            match self.peek() {
+                Some(token) if *token == close => {
+                    self.advance()?;
+                    break;
+                },
+                Some(_) => {},
+                None => {
+                    self.advance()?;
+                    return Err(self.error_at_end("'}'"));
+                },
+            }
+
+            let statement = self.parse_statement()?;
+            let terminated = self.eat(&Token::Delimitter(Delimitter::SemiColon));
+            let (last, end) = match self.peek() {
+                Some(token) => (*token == close, false),
+                None => (false, true),
+            };
+
+            let needs_terminator = statement.needs_terminator();
+            match statement {
+                Statement::Expression(expr) if !terminated && last => {
+                    value = Some(Box::new(expr));
+                },
+                Statement::Block(block) if !terminated && last => {
+                    value = Some(Box::new(Expression::Block(block)));
+                },
+                statement => {
+                    if needs_terminator && !terminated && !last && !end {
+                        self.expect(&Token::Delimitter(Delimitter::SemiColon))?;
+                    }
+                    statements.push(statement);
+                },
+            }
+        }
+
+        Ok(Block { statements, value })
+    }
+
+    /// Parses a function declaration, the `fn` must already be consumed.
+    ///
+    /// The parameters are declared in a scope of their own around the body,
+    /// and loops enclosing the declaration cannot be left from the body.
+    fn parse_function(&mut self) -> Result<Function> {
+        let name = self.expect_identifier()?;
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.clone(), true);
+        }
+
+        self.expect(&Token::Delimitter(Delimitter::LParen))?;
+        let params = self.parse_parameters()?;
+
+        let ret = match self.eat(&Token::Operator(Operator::Misc(MiscOperator::ThinArrow))) {
+            true => Some(self.expect_type()?),
+            false => None,
+        };
+
+        self.expect(&Token::Delimitter(Delimitter::LCurly))?;
+
+        let loops = std::mem::take(&mut self.loops);
+        self.functions += 1;
+        self.scopes.push(
+            params
+                .iter()
+                .map(|param| (param.name.clone(), false))
+                .collect(),
+        );
+        let body = self.parse_block();
+        self.scopes.pop();
+        self.functions -= 1;
+        self.loops = loops;
+
+        Ok(Function {
+            name,
+            params,
+            ret,
+            body: body?,
+        })
+    }
+
+    /// Parses the comma separated parameters of a function upto the closing
+    /// `)`, the `(` must already be consumed. A trailing comma is allowed.
+    fn parse_parameters(&mut self) -> Result<Vec<Parameter>> {
+        let close = Token::Delimitter(Delimitter::RParen);
+        let mut params: Vec<Parameter> = vec![];
+
+        while !self.eat(&close) {
+            let name = self.expect_identifier()?;
+            let span = self.previous;
+
+            if params.iter().any(|param| param.name == name) {
+                let message = format!("duplicate parameter '{}'", name.name());
+                return Err(ParseError::new(message, span).into());
+            }
+
+            let ty = match self.eat(&Token::Delimitter(Delimitter::Colon)) {
+                true => Some(self.expect_type()?),
+                false => None,
+            };
+
+            let default = match self.eat(&Token::Operator(Operator::Misc(MiscOperator::Assign))) {
+                true => Some(self.parse_expression()?),
+                false => None,
+            };
+
+            if default.is_none() && params.iter().any(|param| param.default.is_some()) {
+                let message = format!(
+                    "parameter '{}' without a default follows one with a default",
+                    name.name()
+                );
+                return Err(ParseError::new(message, span).into());
+            }
+
+            params.push(Parameter { name, ty, default });
+
+            if !self.eat(&Token::Delimitter(Delimitter::Comma)) {
+                self.expect(&close)?;
+                break;
+            }
+        }
+
+        Ok(params)
+    }
+
+    /// Parses a `return` with its optional value, only valid inside a
+    /// function.
+    fn parse_return(&mut self) -> Result<Statement> {
+        let token = self.expect_token("'return'")?;
+        if self.functions == 0 {
+            let message = "'return' outside of a function";
+            return Err(ParseError::new(message, token.span).into());
+        }
+
+        let value = match self.peek() {
+            None
+            | Some(Token::Delimitter(Delimitter::SemiColon))
+            | Some(Token::Delimitter(Delimitter::RCurly)) => None,
+            Some(_) => Some(self.parse_expression()?),
+        };
+
+        Ok(Statement::Return(value))
+    }
+
+    fn expect_identifier(&mut self) -> Result<Identifier> {
+        let token = self.expect_token("an identifier")?;
+        match token.node {
+            Token::Identifier(ident) => Ok(ident),
+            _ => Err(Self::unexpected("an identifier", &token)),
+        }
+    }
+
+    fn expect_type(&mut self) -> Result<Identifier> {
+        let token = self.expect_token("a type")?;
+        match token.node {
+            Token::Identifier(ty) => Ok(ty),
+            _ => Err(Self::unexpected("a type", &token)),
+        }
+    }
+
+    /// Parses a `let` or `const` declaration, `keyword` must already be
+    /// consumed. The type annotation is optional while the initializer and the
+    /// terminating `;` are required.
+    pub(crate) fn parse_let(&mut self, keyword: Keyword) -> Result<Statement> {
+        let ident = self.expect_identifier()?;
+        let ty = match self.eat(&Token::Delimitter(Delimitter::Colon)) {
+            true => Some(self.expect_type()?),
+            false => None,
+        };
+
+        self.expect(&Token::Operator(Operator::Misc(MiscOperator::Assign)))?;
+        let expr = self.parse_expression()?;
+        self.expect(&Token::Delimitter(Delimitter::SemiColon))?;
+
+        let constant = keyword == Keyword::Const;
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(ident.clone(), constant);
+        }
+
+        match constant {
+            true => Ok(Statement::Const { ident, ty, expr }),
+            false => Ok(Statement::Let { ident, ty, expr }),
+        }
+    }
+
+    /// Parses an expression, or an assignment when the expression is a name
+    /// followed by an assignment operator.
+    pub(crate) fn parse_expression_statement(&mut self) -> Result<Statement> {
+        let start = self.next_start();
+        let expr = self.parse_expression()?;
+        let target = Span::new(start, self.previous.end());
+
+        let statement = match self.peek().filter(|token| token.is_assignment()) {
+            None => Statement::Expression(expr),
+            Some(_) => {
+                let Expression::Identifier(ident) = expr else {
+                    let message = "invalid left-hand side of assignment";
+                    return Err(ParseError::new(message, target).into());
+                };
+
+                if self.is_constant(&ident) {
+                    let message = format!("cannot assign to constant '{}'", ident.name());
+                    return Err(ParseError::new(message, target).into());
+                }
+
+                let Some(Token::Operator(op)) = self.advance()?.map(|token| token.node) else {
+                    unreachable!("assignments are operators")
+                };
+
+                Statement::Assign {
+                    ident,
+                    op,
+                    expr: self.parse_expression()?,
+                }
+            },
+        };
+
+        Ok(statement)
+    }
+
+    /// Returns `true` if the innermost declaration of `ident` in scope is a
+    /// `const`.
+    fn is_constant(&self, ident: &Identifier) -> bool {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(ident))
+            .copied()
+            .unwrap_or(false)
+    }
+}
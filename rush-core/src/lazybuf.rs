@@ -87,7 +87,7 @@ impl<'a, T: Iterator + 'a> LazyBuf<'a, T> {
             };
 
             let new_ref = &mut *self.ibuf.get();
-            new_ref.extend(ibuff_extension.into_iter());
+            new_ref.extend(ibuff_extension);
 
             new_ref.get(index)
         })
@@ -112,6 +112,25 @@ impl<'a, T: Iterator + 'a> LazyBuf<'a, T> {
         self.get(self.nread)
     }
 
+    /// Get the `n`th item after the next one without modifying the current state, `peek_nth(0)`
+    /// is the same as [`LazyBuf::peek`].
+    ///
+    /// # Example
+    /// ```
+    /// use rush_core::lazybuf::LazyBuf;
+    /// use rush_core::BufferExt;
+    ///
+    /// let     string = String::from("Hello");
+    /// let mut buffer = string.chars().lazy_buf();
+    ///
+    /// assert_eq!(buffer.next().copied(), Some('H'));
+    /// assert_eq!(buffer.peek_nth(2).copied(), Some('l'));
+    /// assert_eq!(buffer.next().copied(), Some('e'));
+    /// ```
+    pub fn peek_nth(&self, n: usize) -> Option<&T::Item> {
+        self.get(self.nread + n)
+    }
+
     /// Consumes the iterator returing a vector of items contained within.
     ///
     /// # Example
@@ -10,7 +10,7 @@ pub mod tracker;
 ///
 /// let string = String::from("Hello world");
 /// let lbuffr = LazyBuf::new(string.chars());
-
+///
 /// assert_eq!(lbuffr.get(5).copied(), string.chars().nth(5));
 /// assert_eq!(lbuffr[5], string.chars().nth(5).unwrap());
 /// assert_eq!(lbuffr[string.len() - 1], string.chars().last().unwrap());
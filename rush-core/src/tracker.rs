@@ -3,15 +3,25 @@ use std::fmt::Display;
 /// Provides a tracker object to better point where an error has occured,
 /// Cannot be changed outside of the `lexer` crate and is for read only
 /// purposes outside.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///
+/// Along with the row and column it keeps the byte offset into the source, so
+/// that a position can be mapped back to the original text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Tracker {
     row: usize,
     col: usize,
+    offset: usize,
+    after_cr: bool,
 }
 
 impl Tracker {
     pub fn new() -> Self {
-        Self { row: 0, col: 0 }
+        Self {
+            row: 0,
+            col: 0,
+            offset: 0,
+            after_cr: false,
+        }
     }
 
     pub fn add_row(&mut self) {
@@ -37,6 +47,63 @@ impl Tracker {
     pub fn get_col(&self) -> usize {
         self.col
     }
+
+    pub fn get_offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Moves the tracker past the given character. A line break starts a new
+    /// row, whether it is `\n`, `\r\n` or a lone `\r`, a byte order mark at
+    /// the very start of the source takes no column, and every other character
+    /// advances the column by one.
+    ///
+    /// # Example
+    /// ```
+    /// use rush_core::tracker::Tracker;
+    ///
+    /// let mut tracker = Tracker::new();
+    /// "ä\nb".chars().for_each(|ch| tracker.advance(ch));
+    ///
+    /// assert_eq!(tracker.get_row(), 1);
+    /// assert_eq!(tracker.get_col(), 1);
+    /// assert_eq!(tracker.get_offset(), 4);
+    ///
+    /// let mut tracker = Tracker::new();
+    /// "\u{feff}a\r\nb\rc".chars().for_each(|ch| tracker.advance(ch));
+    ///
+    /// assert_eq!(tracker.get_row(), 2);
+    /// assert_eq!(tracker.get_col(), 1);
+    /// assert_eq!(tracker.get_offset(), 9);
+    /// ```
+    pub fn advance(&mut self, ch: char) {
+        let at_start = self.offset == 0;
+        self.offset += ch.len_utf8();
+
+        match ch {
+            '\n' if self.after_cr => {},
+            '\n' | '\r' => {
+                self.add_row();
+                self.set_col(0);
+            },
+            '\u{feff}' if at_start => {},
+            _ => self.add_col(),
+        }
+
+        self.after_cr = ch == '\r';
+    }
+
+    /// Moves the tracker by `rows` lines and `bytes` bytes, keeping the column,
+    /// used to relocate positions after an edit to lines preceding them.
+    ///
+    /// # Panics
+    /// If the tracker would be moved before the start of the source.
+    pub fn shift(&mut self, rows: isize, bytes: isize) {
+        self.row = self.row.checked_add_signed(rows).expect("row out of range");
+        self.offset = self
+            .offset
+            .checked_add_signed(bytes)
+            .expect("offset out of range");
+    }
 }
 
 impl Default for Tracker {
@@ -50,3 +117,125 @@ impl Display for Tracker {
         write!(f, "Line: {}, Column: {}", self.row, self.col)
     }
 }
+
+/// A half open range of source positions, `start` points at the first
+/// character of the spanned text and `end` just past its last character.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    start: Tracker,
+    end: Tracker,
+}
+
+impl Span {
+    pub fn new(start: Tracker, end: Tracker) -> Self {
+        Self { start, end }
+    }
+
+    pub fn start(&self) -> Tracker {
+        self.start
+    }
+
+    pub fn end(&self) -> Tracker {
+        self.end
+    }
+
+    /// Length of the spanned text in bytes.
+    pub fn len(&self) -> usize {
+        self.end.offset - self.start.offset
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the smallest span covering both `self` and `other`.
+    ///
+    /// # Example
+    /// ```
+    /// use rush_core::tracker::{Span, Tracker};
+    ///
+    /// let mut tracker = Tracker::new();
+    /// let first = {
+    ///     let start = tracker;
+    ///     tracker.advance('a');
+    ///     Span::new(start, tracker)
+    /// };
+    ///
+    /// tracker.advance(' ');
+    /// let second = {
+    ///     let start = tracker;
+    ///     tracker.advance('b');
+    ///     Span::new(start, tracker)
+    /// };
+    ///
+    /// let joined = first.join(second);
+    /// assert_eq!(joined.start(), first.start());
+    /// assert_eq!(joined.end(), second.end());
+    /// assert_eq!(joined.len(), 3);
+    /// ```
+    pub fn join(self, other: Span) -> Self {
+        let start = if self.start.offset <= other.start.offset {
+            self.start
+        } else {
+            other.start
+        };
+        let end = if self.end.offset >= other.end.offset {
+            self.end
+        } else {
+            other.end
+        };
+
+        Self { start, end }
+    }
+
+    /// Shifts both ends of the span, see [`Tracker::shift`].
+    pub fn shift(&mut self, rows: isize, bytes: isize) {
+        self.start.shift(rows, bytes);
+        self.end.shift(rows, bytes);
+    }
+
+    /// Slices the text covered by this span out of the source it was created from.
+    pub fn source_text<'s>(&self, source: &'s str) -> Option<&'s str> {
+        source.get(self.start.offset..self.end.offset)
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}..{}:{}",
+            self.start.row, self.start.col, self.end.row, self.end.col
+        )
+    }
+}
+
+/// Wraps a value with the [`Span`] of source text it was produced from.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(node: T, span: Span) -> Self {
+        Self { node, span }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Spanned<U> {
+        Spanned {
+            node: f(self.node),
+            span: self.span,
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.node
+    }
+}
+
+impl<T: Display> Display for Spanned<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{}]", self.node, self.span)
+    }
+}
@@ -1,5 +1,5 @@
 use char_reader::ReadChars;
-use lexer::Lexer;
+use lexer::{Lexer, Mode};
 use std::{
     fs::File,
     io::{Cursor, IsTerminal, Read, Write},
@@ -26,10 +26,12 @@ fn read_prompt() -> std::io::Result<()> {
     println!();
 
     loop {
-        buf.clear();
         line_counter += 1;
 
-        print!("rush:[{:0>3}]> ", line_counter);
+        match buf.is_empty() {
+            true => print!("rush:[{:0>3}]> ", line_counter),
+            false => print!("{:>12}", "...> "),
+        }
         std::io::stdout().flush()?;
 
         if std::io::stdin().read_line(&mut buf)? == 0 || buf.trim() == "exit" {
@@ -37,10 +39,24 @@ fn read_prompt() -> std::io::Result<()> {
             break;
         }
 
-        Lexer::new(Box::new(buf.as_str().chars())).for_each(|x| match x {
+        let completeness = Lexer::from_source(&buf)
+            .with_mode(Mode::Command)
+            .completeness();
+        if !completeness.is_complete() {
+            continue;
+        }
+
+        let mut lexer = Lexer::from_source(&buf).with_mode(Mode::Command);
+        lexer.by_ref().for_each(|x| match x {
             Ok(token) => println!("{token}"),
             Err(e) => eprintln!("{e}"),
         });
+        lexer
+            .warnings()
+            .iter()
+            .for_each(|w| eprintln!("Warning: {w}"));
+
+        buf.clear();
     }
 
     Ok(())
@@ -57,10 +73,15 @@ fn main() -> anyhow::Result<()> {
         None => {
             let mut string = String::new();
             std::io::stdin().read_to_string(&mut string)?;
-            Lexer::new(Box::new(string.chars())).for_each(|t| match t {
+            let mut lexer = Lexer::from_source(&string);
+            lexer.by_ref().for_each(|t| match t {
                 Ok(token) => println!("{token}"),
                 Err(e) => eprintln!("{e}"),
             });
+            lexer
+                .warnings()
+                .iter()
+                .for_each(|w| eprintln!("Warning: {w}"));
         },
     }
 